@@ -1,12 +1,41 @@
-use crate::demo::{DemoFrame, Vector3};
+use crate::demo::{DemoFrame, InputButtons, Vector3};
 use std::{f32::consts::PI};
 use crate::bspfile::{BspData};
+use crate::player::DUCK_VIEW_HEIGHT;
+use crate::weapon::Weapon;
 
 #[derive(Debug)]
 pub struct JumpSegment<'a> {
     pub start_index: usize,
     pub end_index: usize,
-    pub frames:  &'a [DemoFrame]
+    pub frames:  &'a [DemoFrame],
+    /// 기술을 시작할 때 들고 있던 무기. 알 수 없으면 None
+    pub weapon: Option<Weapon>,
+    /// 기술 도중 무기를 바꿨는지
+    pub weapon_switched: bool,
+}
+
+impl<'a> JumpSegment<'a> {
+    fn new(frames: &'a [DemoFrame], start_index: usize, end_index: usize) -> Self {
+        let frames = &frames[start_index..=end_index];
+        let weapon = frames[0].current_weapon;
+
+        JumpSegment {
+            start_index,
+            end_index,
+            frames,
+            weapon,
+            weapon_switched: frames.iter().any(|frame| frame.current_weapon != weapon),
+        }
+    }
+
+    /// 기술 중 가장 높았던 최대 속도. 무기를 바꿨다면 빠른 무기 쪽을 돌려준다.
+    pub fn maxspeed(&self) -> f32 {
+        self.frames
+            .iter()
+            .map(DemoFrame::maxspeed)
+            .fold(0.0, f32::max)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -16,11 +45,27 @@ pub struct MovementData {
 }
 
 
-const CONTENTS_EMPTY: i32 = 0;
-const CONTENTS_SOLID: i32 = -1;
+pub const CONTENTS_EMPTY: i32 = 0;
+pub const CONTENTS_SOLID: i32 = -1;
+
+/// PM_AirAccelerate 에서 wishspeed 로 더할 수 있는 속도 상한
+const AIR_WISHSPEED_CAP: f32 = 30.0;
+
+/// 서 있는 플레이어와 앉은 플레이어의 클립 헐 (headnode 인덱스)
+pub const HULL_STANDING: usize = 1;
+pub const HULL_DUCKED: usize = 3;
+
+/// PM_Friction 이 헐 바닥에서 아래로 긋는 거리
+const EDGE_TRACE_DEPTH: f32 = 34.0;
 
+/// 서 있는 플레이어의 클립 헐에서 막힌 지점인지
 pub fn is_clipped(point: Vector3, bsp: &BspData) -> bool {
-    let mut node_index = bsp.models[0].headnode[1]; // clipnode root
+    is_clipped_in_hull(point, bsp, HULL_STANDING)
+}
+
+/// `hull` 클립 헐에서 막힌 지점인지
+pub fn is_clipped_in_hull(point: Vector3, bsp: &BspData, hull: usize) -> bool {
+    let mut node_index = bsp.models[0].headnode[hull]; // clipnode root
 
     loop {
         if node_index < 0 {
@@ -84,34 +129,68 @@ pub fn angle_vectors (angle:&Vector3) -> (Vector3, Vector3, Vector3) {
     }
 
 
-pub fn pm_friction(state: &DemoFrame, map_data: &BspData) -> Vector3 {
-    if !state.onground {
-        return Vector3 { x: (0.0), y: (0.0), z: (0.0) }
-    }
-    let speed = state.simvel.length();
-
-    let mut start: Vector3 = Vector3 {x:(0.0), y:(0.0), z:(0.0)};
-    let mut stop: Vector3 = Vector3 {x:(0.0), y:(0.0), z:(0.0)};
-    // println!("vh: {:?}, cmd: {:?},spd: {:?}", state.viewheight, state.command, speed);
+/// `start` 에서 `stop` 까지의 선분이 `hull` 클립 헐에서 막히는지.
+/// PM_PlayerTrace 대신 1 유닛 간격으로 점을 찍어 본다.
+fn trace_hits(start: Vector3, stop: Vector3, bsp: &BspData, hull: usize) -> bool {
+    let delta = Vector3 {
+        x: stop.x - start.x,
+        y: stop.y - start.y,
+        z: stop.z - start.z,
+    };
+    let steps = delta.length_3d().ceil().max(1.0) as usize;
+
+    (0..=steps).any(|step| {
+        let t = step as f32 / steps as f32;
+        let point = Vector3 {
+            x: start.x + delta.x * t,
+            y: start.y + delta.y * t,
+            z: start.z + delta.z * t,
+        };
 
-    let mut custom_simorg = state.simorg;
-    custom_simorg.z -= 400.0;
-    let trace: bool = is_clipped(custom_simorg, map_data);
-    //println!("trace: {}",trace);
+        is_clipped_in_hull(point, bsp, hull)
+    })
+}
 
-    start.x = state.simorg.x + state.simvel.x * 16.0;
-    start.y = state.simorg.y + state.simvel.y * 16.0;
-    start.z = state.simorg.z + state.simvel.z * 16.0;
+/// PM_Friction. 지면 마찰을 적용한 속도를 돌려준다. 공중이면 속도 그대로다.
+///
+/// 진행 방향 16 유닛 앞의 헐 바닥에서 34 유닛 아래까지 아무것도 없으면 edgefriction 을 곱하고,
+/// 느릴 때는 sv_stopspeed 만큼의 속도로 감속한다.
+pub fn pm_friction(state: &DemoFrame, map_data: &BspData) -> Vector3 {
+    let speed = state.simvel.length_3d();
+    if !state.onground || speed < 0.1 {
+        return state.simvel;
+    }
 
-    if state.viewheight.z == 12.0 {
-        //앉은 상태
-    } else if state.viewheight.z == 17.0 {
-        //서 있는 상태
+    // 앉은 상태면 헐 바닥이 -18, 서 있으면 -36
+    let (hull, hull_bottom) = if state.viewheight.z == DUCK_VIEW_HEIGHT {
+        (HULL_DUCKED, -18.0)
     } else {
-        //앉는 도중
+        (HULL_STANDING, -36.0)
+    };
+    let start = Vector3 {
+        x: state.simorg.x + state.simvel.x / speed * 16.0,
+        y: state.simorg.y + state.simvel.y / speed * 16.0,
+        z: state.simorg.z + hull_bottom,
     };
+    let stop = Vector3 {
+        z: start.z - EDGE_TRACE_DEPTH,
+        ..start
+    };
+
+    let mut friction = state.movevars.friction;
+    if !trace_hits(start, stop, map_data, hull) {
+        friction *= state.movevars.edgefriction;
+    }
+
+    let control = speed.max(state.movevars.stopspeed);
+    let drop = control * friction * state.frametime;
+    let scale = (speed - drop).max(0.0) / speed;
 
-    Vector3 {x:(0.0), y:(0.0), z:(0.0)}
+    Vector3 {
+        x: state.simvel.x * scale,
+        y: state.simvel.y * scale,
+        z: state.simvel.z * scale,
+    }
 }
 
 /// PM_PlayerTrace 의 단순화 버전.
@@ -150,64 +229,66 @@ pub fn pm_player_trace(state: &DemoFrame, map_data: &BspData) -> (bool, Vector3)
     (is_edge, sample_point)
 }
 
+/// PM_Accelerate. 지면 가속을 적용한 속도. 공중이면 속도 그대로다.
 pub fn accelerate(state: &DemoFrame) -> Vector3 {
     if !state.onground {
-        return Vector3 {x:0.0,y:0.0,z:0.0};
+        return state.simvel;
     }
 
-    let (forward, right, up) = angle_vectors(&state.viewangle);
-    
-    let wishvel = Vector3 {
-        x: forward.x * state.forwardmove + right.x * state.sidemove + up.x * state.upmove,
-        y: forward.y * state.forwardmove + right.y * state.sidemove + up.y * state.upmove,
-        z: forward.z * state.forwardmove  + right.z * state.sidemove  + up.z * state.upmove
-    };
-
-    
-    return Vector3  {x:0.0,y:0.0,z:0.0};
-}
+    let (wishdir, wishspeed) = wish_velocity(state);
 
-pub fn airaccelerate(state: &DemoFrame) -> Vector3 {
-    if state.onground {
-        return Vector3 {x:0.0,y:0.0,z:0.0};
+    let addspeed = wishspeed - state.simvel.dot(wishdir);
+    if addspeed <= 0.0 {
+        return state.simvel;
     }
 
-    let (forward, right, _up) = angle_vectors(&state.viewangle);
-    
-    let wishvel = Vector3 {
-        x: forward.x * state.forwardmove + right.x * state.sidemove,
-        y: forward.y * state.forwardmove + right.y * state.sidemove,
-        z: forward.z * state.forwardmove + right.z * state.sidemove
-    };
+    let accelspeed = (state.accelerate * state.frametime * wishspeed).min(addspeed);
 
-    let mut wishspd:f32 = wishvel.length();
-    if wishspd > 30.0 {
-        wishspd = 30.0;
+    Vector3 {
+        x: state.simvel.x + accelspeed * wishdir.x,
+        y: state.simvel.y + accelspeed * wishdir.y,
+        z: state.simvel.z + accelspeed * wishdir.z,
     }
+}
 
-    let wishdir = wishvel.normalize();
+/// PM_AirAccelerate. 공중 가속을 적용한 속도. 지면이면 속도 그대로다.
+pub fn airaccelerate(state: &DemoFrame) -> Vector3 {
+    if state.onground {
+        return state.simvel;
+    }
 
-    let currentspeed = state.simvel.dot(wishdir);
+    let (wishdir, wishspeed) = wish_velocity(state);
 
-    let mut addspeed = wishspd - currentspeed;
-    
+    // 속도를 더할 수 있는 한계는 30 이지만 가속량은 제한 전 wishspeed 로 계산한다
+    let addspeed = wishspeed.min(AIR_WISHSPEED_CAP) - state.simvel.dot(wishdir);
     if addspeed <= 0.0 {
-        addspeed = 0.0;
+        return state.simvel;
     }
 
-    let mut accelspeed: f32 = state.airaccelerate * state.frametime * wishvel.length() * 1.0;
-    
-    if accelspeed > addspeed {
-        accelspeed = addspeed;
-    };
-    
+    let accelspeed = (state.airaccelerate * state.frametime * wishspeed).min(addspeed);
 
-    let final_speed = Vector3{
+    Vector3 {
         x: state.simvel.x + accelspeed * wishdir.x,
         y: state.simvel.y + accelspeed * wishdir.y,
-        z: state.simvel.y + accelspeed * wishdir.z
+        z: state.simvel.z + accelspeed * wishdir.z,
+    }
+}
+
+/// PM_WalkMove / PM_AirMove 의 wishdir 과 최대 속도(무기, sv_maxspeed)로 제한한 wishspeed
+fn wish_velocity(state: &DemoFrame) -> (Vector3, f32) {
+    let (forward, right, _up) = angle_vectors(&state.viewangle);
+
+    // 이동 방향은 수평면에서만 본다
+    let forward = Vector3 { x: forward.x, y: forward.y, z: 0.0 }.normalize();
+    let right = Vector3 { x: right.x, y: right.y, z: 0.0 }.normalize();
+
+    let wishvel = Vector3 {
+        x: forward.x * state.forwardmove + right.x * state.sidemove,
+        y: forward.y * state.forwardmove + right.y * state.sidemove,
+        z: 0.0,
     };
-    return final_speed
+
+    (wishvel.normalize(), wishvel.length().min(state.maxspeed()))
 }
 
 pub fn strafe_optimize(state: &DemoFrame) -> (f32, Vector3) {
@@ -245,12 +326,21 @@ pub fn strafe_optimize(state: &DemoFrame) -> (f32, Vector3) {
         }
     }
 
-    let original = airaccelerate(&state);
+    let _original = airaccelerate(state);
 
-    return (max_speed, Vector3{x:state.viewangle.x, y:optimized_param, z:state.viewangle.z})
+    (max_speed, Vector3{x:state.viewangle.x, y:optimized_param, z:state.viewangle.z})
 }
 
 
+/// 이전 프레임에는 없던 버튼이 이 프레임에 눌렸는지.
+/// 커멘드 프레임의 +jump 는 다음 프레임의 usercmd 버튼에 들어간다.
+/// 입력이 없는 다른 플레이어 프레임은 움직임으로 추정한 버튼을 본다.
+fn pressed(frames: &[DemoFrame], index: usize, button: InputButtons) -> bool {
+    let held = |frame: &DemoFrame| (frame.buttons | frame.estimated_buttons).contains(button);
+
+    held(&frames[index]) && (index == 0 || !held(&frames[index - 1]))
+}
+
 /// 점프 세그먼트 추출 함수
 pub fn extract_jump_segments<'a>(frames: &'a [DemoFrame], map_data: &BspData) -> Vec<JumpSegment<'a>> {
     let mut segments: Vec<JumpSegment<'a>> = Vec::new();
@@ -287,11 +377,7 @@ pub fn extract_jump_segments<'a>(frames: &'a [DemoFrame], map_data: &BspData) ->
                 if frames_on_ground > 10 {
                     if let Some(start) = current_segment_start {
                         let end = last_segment_index.saturating_sub(1).max(start);
-                        segments.push(JumpSegment {
-                            start_index: start,
-                            end_index: end,
-                            frames: &frames[start..=end],
-                        });
+                        segments.push(JumpSegment::new(frames, start, end));
                     }
                     // 시퀀스 초기화
                     is_sequenced = false;
@@ -310,12 +396,7 @@ pub fn extract_jump_segments<'a>(frames: &'a [DemoFrame], map_data: &BspData) ->
         let mut started_this_frame = false;
 
         // duck 감지
-        if i >= 2 && frames[i - 2].onground
-            && frames[i - 2]
-                .command
-                .iter()
-                .any(|cmd| cmd == "+duck")
-        {
+        if i >= 2 && frames[i - 2].onground && pressed(frames, i - 1, InputButtons::DUCK) {
             if !is_sequenced {
                 is_sequenced = true;
                 // 2프레임 이전부터 이전 프레임 체크
@@ -352,12 +433,7 @@ pub fn extract_jump_segments<'a>(frames: &'a [DemoFrame], map_data: &BspData) ->
         }
 
         // 점프 감지
-        if i >= 1 && frames[i - 1].onground
-            && frames[i - 1]
-                .command
-                .iter()
-                .any(|cmd| cmd == "+jump")
-        {
+        if i >= 1 && frames[i - 1].onground && pressed(frames, i, InputButtons::JUMP) {
             if !is_sequenced {
                 is_sequenced = true;
                 // 점프 세그먼트 감지
@@ -407,21 +483,15 @@ pub fn extract_jump_segments<'a>(frames: &'a [DemoFrame], map_data: &BspData) ->
     }
 
     // 시퀀스가 종료되지 않았다면 처리할 로직
-    if is_sequenced {
-        if let Some(start) = current_segment_start {
-            let end = last_segment_index.max(start);
-            segments.push(JumpSegment {
-                start_index: start,
-                end_index: end,
-                frames: &frames[start..=end],
-            });
-        }
+    if is_sequenced && let Some(start) = current_segment_start {
+        let end = last_segment_index.max(start);
+        segments.push(JumpSegment::new(frames, start, end));
     }
 
 
     
     //첫 프레임 제거 - 플러그인 녹화 로직상 첫프레임은 텔포로 잡힘
-    if segments.len() > 0 {
+    if !segments.is_empty() {
         segments[0].frames = &segments[0].frames[1..];
     }
 
@@ -0,0 +1,571 @@
+//! 데모 익명화
+//!
+//! `MsgDataParseMode::Raw` 로 읽은 Demo 에서 플레이어 이름, SteamID, 채팅, `name`/`say` 커멘드를 바꾸거나 지운다.
+//! 네트워크 메세지는 해당 메세지의 바이트만 바꿔 끼우므로 엔티티, clientdata 같은 움직임 데이터는 원본 그대로 남는다.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::parse_netmsg::{
+    SVC_CENTERPRINT, SVC_PRINT, SVC_STUFFTEXT, SVC_TEMPENTITY, SVC_UPDATEUSERINFO, TE_TEXTMESSAGE,
+    parse_netmsg_spans,
+};
+use crate::player::info_value;
+use crate::types::{
+    Aux, AuxRefCell, ConsoleCommand, Demo, EngineMessage, Frame, FrameData, MessageData,
+    NetMessage, NetworkMessage, SvcUpdateUserInfo, UserMessage, bytes_to_string,
+};
+use crate::usermsg::CsUserMessage;
+
+/// 콘솔 커멘드 프레임의 고정 길이
+const CONSOLE_COMMAND_SIZE: usize = 64;
+
+/// userinfo 에서 지우는 키. `*sid` 는 SteamID64 이다.
+const USER_INFO_REMOVED_KEYS: [&str; 1] = ["*sid"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplacedField {
+    /// userinfo 의 name
+    Name,
+    /// userinfo 의 *sid
+    SteamId,
+    /// svc_updateuserinfo 의 CD 키 해시
+    CdKeyHash,
+    /// SayText 사용자 메세지
+    Chat,
+    /// 콘솔 커멘드 프레임의 name, say, say_team
+    ConsoleCommand,
+    /// 서버가 보낸 svc_stufftext 의 name, say, say_team
+    StuffText,
+    /// TextMsg 인자, svc_print, svc_centerprint, HUD 텍스트 속의 이름
+    Text,
+}
+
+/// 바꾸거나 지운 값 하나
+#[derive(Debug, Clone)]
+pub struct Replacement {
+    pub time: f32,
+    pub frame: i32,
+    pub field: ReplacedField,
+    pub original: String,
+    /// 지웠으면 None
+    pub replacement: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AnonymizeReport {
+    /// 원래 이름과 바꾼 이름
+    pub names: BTreeMap<String, String>,
+    pub replacements: Vec<Replacement>,
+    /// 해석하지 못한 부분이 있어 끝까지 확인하지 못한 네트워크 메세지 프레임 수.
+    /// 0 이 아니면 그 프레임에 이름이나 채팅이 남아 있을 수 있다.
+    pub unchecked_frames: usize,
+}
+
+/// 이름은 슬롯 번호로 만든 `Player<n>` 으로 바꾸고, SteamID, CD 키 해시, 채팅은 지운다.
+/// 커멘드 프레임과 svc_stufftext 의 `name` 은 데모를 녹화한 플레이어의 별명으로, `say`/`say_team` 은 빈 커멘드로 바꾼다.
+/// 접속 메세지처럼 userinfo 보다 먼저 오는 텍스트도 바꿀 수 있도록 이름을 미리 모아 둔다.
+pub fn anonymize_demo(demo: &Demo) -> (Demo, AnonymizeReport) {
+    let mut anonymizer = Anonymizer {
+        aux: Aux::new_ref_cell(),
+        pov_slot: None,
+        report: AnonymizeReport {
+            names: collect_names(demo),
+            ..Default::default()
+        },
+    };
+
+    let mut demo = demo.clone();
+    for entry in &mut demo.directory.entries {
+        for frame in &mut entry.frames {
+            anonymizer.frame(frame);
+        }
+    }
+
+    (demo, anonymizer.report)
+}
+
+/// 모든 svc_updateuserinfo 의 이름과 별명
+fn collect_names(demo: &Demo) -> BTreeMap<String, String> {
+    let aux = Aux::new_ref_cell();
+    let mut names = BTreeMap::new();
+
+    for frame in demo
+        .directory
+        .entries
+        .iter()
+        .flat_map(|entry| &entry.frames)
+    {
+        let FrameData::NetworkMessage(message) = &frame.frame_data else {
+            continue;
+        };
+
+        for (_, parsed) in raw_messages(&message.1, &aux).unwrap_or_default() {
+            if let NetMessage::EngineMessage(engine_message) = parsed
+                && let EngineMessage::SvcUpdateUserInfo(info) = *engine_message
+                && let Some(name) = info_value(&bytes_to_string(&info.user_info), "name")
+                && !name.is_empty()
+            {
+                names.insert(name.to_string(), alias(info.index));
+            }
+        }
+    }
+
+    names
+}
+
+/// Raw 페이로드를 메세지 단위로 나눈다. Raw 가 아니면 None.
+fn raw_messages(
+    message: &NetworkMessage,
+    aux: &AuxRefCell,
+) -> Option<Vec<(Range<usize>, NetMessage)>> {
+    let MessageData::Raw(payload) = &message.messages else {
+        return None;
+    };
+
+    aux.borrow_mut().is_hltv = message.info.ref_params.spectator != 0;
+
+    Some(parse_netmsg_spans(payload, aux))
+}
+
+struct Anonymizer {
+    /// 사용자 메세지 크기와 델타 디코더를 알아야 메세지 경계를 찾을 수 있다
+    aux: AuxRefCell,
+    /// svc_serverinfo 의 player_index
+    pov_slot: Option<u8>,
+    report: AnonymizeReport,
+}
+
+impl Anonymizer {
+    fn frame(&mut self, frame: &mut Frame) {
+        let (time, frame_number) = (frame.time, frame.frame);
+
+        match &mut frame.frame_data {
+            FrameData::NetworkMessage(message) => {
+                self.network_message(&mut message.1, time, frame_number)
+            }
+            FrameData::ConsoleCommand(command) => {
+                let original = command.command();
+                if let Some(replaced) = self.scrub_commands(&original) {
+                    let mut bytes = replaced.clone().into_bytes();
+                    // 널 뒤에 남아 있던 이전 커멘드 바이트까지 지운다
+                    bytes.resize(CONSOLE_COMMAND_SIZE, 0);
+                    *command = ConsoleCommand { command: bytes };
+
+                    self.replaced(
+                        time,
+                        frame_number,
+                        ReplacedField::ConsoleCommand,
+                        original,
+                        replaced,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn network_message(&mut self, message: &mut NetworkMessage, time: f32, frame: i32) {
+        let (Some(messages), MessageData::Raw(payload)) =
+            (raw_messages(message, &self.aux), &message.messages)
+        else {
+            self.report.unchecked_frames += 1;
+            return;
+        };
+
+        let mut scrubbed = Vec::with_capacity(payload.len());
+        let mut changed = false;
+
+        for (span, parsed) in messages {
+            let replacement = match &parsed {
+                NetMessage::EngineMessage(engine_message) => match engine_message.as_ref() {
+                    EngineMessage::SvcServerInfo(info) => {
+                        self.pov_slot = Some(info.player_index);
+                        None
+                    }
+                    EngineMessage::SvcUpdateUserInfo(info) => self.user_info(info, time, frame),
+                    EngineMessage::SvcStuffText(stuff_text) => {
+                        self.stuff_text(&stuff_text.command, time, frame)
+                    }
+                    EngineMessage::SvcPrint(print) => {
+                        self.text(&[SVC_PRINT], &print.message, time, frame)
+                    }
+                    EngineMessage::SvcCenterPrint(print) => {
+                        self.text(&[SVC_CENTERPRINT], &print.message, time, frame)
+                    }
+                    EngineMessage::SvcTempEntity(temp_entity)
+                        if temp_entity.entity_type == TE_TEXTMESSAGE =>
+                    {
+                        // channel, x, y, effect, 색상, fade in/out, hold time 뒤에 effect 2 면 fx time 이 온다.
+                        // 파서가 문자열까지 읽었으므로 길이는 충분하다.
+                        let entity = &temp_entity.entity;
+                        let (fields, text) = entity.split_at(if entity[5] == 2 { 22 } else { 20 });
+                        let prefix = [&[SVC_TEMPENTITY, TE_TEXTMESSAGE], fields].concat();
+
+                        self.text(&prefix, text, time, frame)
+                    }
+                    _ => None,
+                },
+                NetMessage::UserMessage(user_message) => {
+                    // 길이 바이트가 있는 가변 길이 메세지만 길이를 바꿔 쓸 수 있다
+                    let variable = span.len() == user_message.data.len() + 2;
+                    self.user_message(user_message, variable, time, frame)
+                }
+                NetMessage::Unparsed(_) => {
+                    self.report.unchecked_frames += 1;
+                    None
+                }
+            };
+
+            match replacement {
+                Some(bytes) => {
+                    scrubbed.extend_from_slice(&bytes);
+                    changed = true;
+                }
+                None => scrubbed.extend_from_slice(&payload[span]),
+            }
+        }
+
+        if changed {
+            message.message_length = scrubbed.len() as i32;
+            message.messages = MessageData::Raw(scrubbed);
+        }
+    }
+
+    /// 바꾼 svc_updateuserinfo 메세지 전체 바이트
+    fn user_info(&mut self, info: &SvcUpdateUserInfo, time: f32, frame: i32) -> Option<Vec<u8>> {
+        let original = bytes_to_string(&info.user_info);
+        let mut changed = false;
+        let mut pairs = vec![];
+
+        let mut parts = original.strip_prefix('\\').unwrap_or(&original).split('\\');
+        while let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+            if key == "name" {
+                let alias = alias(info.index);
+                self.replaced(
+                    time,
+                    frame,
+                    ReplacedField::Name,
+                    value.to_string(),
+                    alias.clone(),
+                );
+                pairs.push((key, alias));
+                changed = true;
+            } else if USER_INFO_REMOVED_KEYS.contains(&key) {
+                self.removed(time, frame, ReplacedField::SteamId, value.to_string());
+                changed = true;
+            } else {
+                pairs.push((key, value.to_string()));
+            }
+        }
+
+        if info.cd_key_hash.iter().any(|&b| b != 0) {
+            let original = info
+                .cd_key_hash
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            self.removed(time, frame, ReplacedField::CdKeyHash, original);
+            changed = true;
+        }
+
+        if !changed {
+            return None;
+        }
+
+        let mut bytes = vec![SVC_UPDATEUSERINFO, info.index];
+        bytes.extend_from_slice(&info.id.to_le_bytes());
+        for (key, value) in pairs {
+            bytes.extend_from_slice(format!("\\{}\\{}", key, value).as_bytes());
+        }
+        bytes.push(0);
+        bytes.extend_from_slice(&[0; 16]);
+
+        Some(bytes)
+    }
+
+    fn stuff_text(&mut self, command: &[u8], time: f32, frame: i32) -> Option<Vec<u8>> {
+        let original = bytes_to_string(command);
+        let replaced = self.scrub_commands(&original)?;
+
+        let mut bytes = vec![SVC_STUFFTEXT];
+        bytes.extend_from_slice(replaced.as_bytes());
+        bytes.push(0);
+
+        self.replaced(time, frame, ReplacedField::StuffText, original, replaced);
+
+        Some(bytes)
+    }
+
+    /// 널 종료 문자열로 끝나는 메세지에서 이름을 바꾼 전체 바이트. `prefix` 는 문자열 앞까지다.
+    fn text(&mut self, prefix: &[u8], text: &[u8], time: f32, frame: i32) -> Option<Vec<u8>> {
+        let original = bytes_to_string(text);
+        let replaced = self.replace_names(&original)?;
+
+        let mut bytes = prefix.to_vec();
+        bytes.extend_from_slice(replaced.as_bytes());
+        bytes.push(0);
+
+        self.replaced(time, frame, ReplacedField::Text, original, replaced);
+
+        Some(bytes)
+    }
+
+    /// SayText 는 메세지를 통째로 지우고(빈 바이트), TextMsg 는 인자 속 이름을 바꾼다.
+    fn user_message(
+        &mut self,
+        message: &UserMessage,
+        variable: bool,
+        time: f32,
+        frame: i32,
+    ) -> Option<Vec<u8>> {
+        match message.name().as_str() {
+            "SayText" => {}
+            "TextMsg" if variable => return self.text_msg(message, time, frame),
+            _ => return None,
+        }
+
+        let original = match CsUserMessage::parse(message) {
+            CsUserMessage::SayText(say_text) => {
+                let mut text = vec![say_text.message];
+                text.extend(say_text.params);
+                text.join(" ")
+            }
+            _ => String::from_utf8_lossy(&message.data).into_owned(),
+        };
+        self.removed(time, frame, ReplacedField::Chat, original);
+
+        Some(vec![])
+    }
+
+    fn text_msg(&mut self, message: &UserMessage, time: f32, frame: i32) -> Option<Vec<u8>> {
+        let CsUserMessage::TextMsg(text_msg) = CsUserMessage::parse(message) else {
+            return None;
+        };
+
+        // 번역 키(#Game_connected 등)는 그대로 두고 인자만 바꾼다
+        let params: Vec<Option<String>> = text_msg
+            .params
+            .iter()
+            .map(|param| self.replace_names(param))
+            .collect();
+        if params.iter().all(Option::is_none) {
+            return None;
+        }
+
+        let mut data = vec![text_msg.destination];
+        data.extend_from_slice(text_msg.message.as_bytes());
+        data.push(0);
+        for (original, replaced) in text_msg.params.iter().zip(&params) {
+            data.extend_from_slice(replaced.as_deref().unwrap_or(original).as_bytes());
+            data.push(0);
+        }
+
+        let length = u8::try_from(data.len()).ok()?;
+        for (original, replaced) in text_msg.params.into_iter().zip(params) {
+            if let Some(replaced) = replaced {
+                self.replaced(time, frame, ReplacedField::Text, original, replaced);
+            }
+        }
+
+        let mut bytes = vec![message.id, length];
+        bytes.extend_from_slice(&data);
+
+        Some(bytes)
+    }
+
+    fn replace_names(&self, text: &str) -> Option<String> {
+        replace_names(text, &self.report.names)
+    }
+
+    /// `;` 나 줄바꿈으로 이어진 커멘드 중 name, say, say_team 만 바꾼다. 바꾼 게 없으면 None.
+    fn scrub_commands(&self, commands: &str) -> Option<String> {
+        let mut scrubbed = String::with_capacity(commands.len());
+        let mut changed = false;
+
+        for command in commands.split_inclusive([';', '\n']) {
+            let body = command.trim_end_matches([';', '\n']);
+            let separator = &command[body.len()..];
+            let trimmed = body.trim_start();
+            let name = trimmed.split_whitespace().next().unwrap_or_default();
+
+            scrubbed.push_str(&body[..body.len() - trimmed.len()]);
+            match name.to_ascii_lowercase().as_str() {
+                "name" => {
+                    let alias = self.pov_slot.map_or_else(|| "Player".to_string(), alias);
+                    scrubbed.push_str(&format!("name \"{}\"", alias));
+                    changed = true;
+                }
+                "say" | "say_team" => changed = true,
+                _ => scrubbed.push_str(trimmed),
+            }
+            scrubbed.push_str(separator);
+        }
+
+        changed.then_some(scrubbed)
+    }
+
+    fn replaced(
+        &mut self,
+        time: f32,
+        frame: i32,
+        field: ReplacedField,
+        original: String,
+        replacement: String,
+    ) {
+        self.report.replacements.push(Replacement {
+            time,
+            frame,
+            field,
+            original,
+            replacement: Some(replacement),
+        });
+    }
+
+    fn removed(&mut self, time: f32, frame: i32, field: ReplacedField, original: String) {
+        self.report.replacements.push(Replacement {
+            time,
+            frame,
+            field,
+            original,
+            replacement: None,
+        });
+    }
+}
+
+/// 슬롯 번호(0 부터)로 만든 별명
+fn alias(slot: u8) -> String {
+    format!("Player{}", slot as u32 + 1)
+}
+
+/// 알고 있는 이름을 별명으로 바꾼다. 바꾼 게 없으면 None.
+///
+/// 앞뒤가 다른 낱말과 붙어 있지 않은 이름만 바꾸고, 번역 키(`#Game_connected` 등)는 건드리지 않는다.
+/// 한 번에 훑으며 같은 위치에서는 긴 이름을 먼저 맞추므로, 다른 이름에 포함된 이름이나
+/// 이미 바꾼 별명(`Player1` 이라는 이름의 플레이어가 있어도)이 다시 바뀌지 않는다.
+fn replace_names(text: &str, names: &BTreeMap<String, String>) -> Option<String> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut names: Vec<_> = names.iter().filter(|(name, _)| !name.is_empty()).collect();
+    names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+    let mut replaced = String::with_capacity(text.len());
+    let mut changed = false;
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let previous = text[..text.len() - rest.len()].chars().next_back();
+
+        if c == '#' && !previous.is_some_and(is_word) {
+            let key = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            replaced.push_str(&rest[..key]);
+            rest = &rest[key..];
+            continue;
+        }
+
+        let matched = names.iter().find(|(name, _)| {
+            let Some(after) = rest.strip_prefix(name.as_str()) else {
+                return false;
+            };
+
+            // 이름 끝이 낱말 글자일 때만 앞뒤 글자가 이어지는지 본다
+            let separated = |inner: Option<char>, outer: Option<char>| {
+                !(inner.is_some_and(is_word) && outer.is_some_and(is_word))
+            };
+
+            separated(name.chars().next(), previous)
+                && separated(name.chars().next_back(), after.chars().next())
+        });
+
+        match matched {
+            Some((name, alias)) => {
+                replaced.push_str(alias);
+                rest = &rest[name.len()..];
+                changed = true;
+            }
+            None => {
+                replaced.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    changed.then_some(replaced)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::parse::{MsgDataParseMode, parse_demo};
+    use crate::write::DemoWriter;
+
+    fn names() -> BTreeMap<String, String> {
+        [
+            ("a", "Player1"),
+            ("Game", "Player2"),
+            ("desu", "Player3"),
+            ("desu2", "Player4"),
+            ("Player1", "Player5"),
+        ]
+        .into_iter()
+        .map(|(name, alias)| (name.to_string(), alias.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn replaces_whole_names_only() {
+        let names = names();
+
+        assert_eq!(
+            replace_names("a is the best at aiming", &names).as_deref(),
+            Some("Player1 is the best at aiming")
+        );
+        assert_eq!(
+            replace_names("desu2 killed desu with a Game", &names).as_deref(),
+            Some("Player4 killed Player3 with Player1 Player2")
+        );
+        assert_eq!(replace_names("desudesu and Gamer", &names), None);
+        assert_eq!(replace_names("#Game_connected", &names), None);
+    }
+
+    #[test]
+    fn does_not_replace_aliases_again() {
+        assert_eq!(
+            replace_names("Player1 said hi to a", &names()).as_deref(),
+            Some("Player5 said hi to Player1")
+        );
+    }
+    #[test]
+    fn anonymized_demo_has_no_names_or_steam_ids() {
+        let bytes = std::fs::read("test/274_dcj_Desu.dem").unwrap();
+        let (_, demo) = parse_demo(&bytes, MsgDataParseMode::Raw).unwrap();
+        let (anonymized, report) = anonymize_demo(&demo);
+        let written = DemoWriter::write_demo(Cursor::new(vec![]), &anonymized)
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(report.unchecked_frames, 0);
+        assert!(parse_demo(&written, MsgDataParseMode::Parse).is_ok());
+
+        let contains =
+            |bytes: &[u8], needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+        let steam_ids: Vec<&String> = report
+            .replacements
+            .iter()
+            .filter(|replacement| replacement.field == ReplacedField::SteamId)
+            .map(|replacement| &replacement.original)
+            .collect();
+
+        assert!(!report.names.is_empty() && !steam_ids.is_empty());
+        for original in report.names.keys().chain(steam_ids) {
+            assert!(contains(&bytes, original.as_bytes()));
+            assert!(
+                !contains(&written, original.as_bytes()),
+                "{} is left",
+                original
+            );
+        }
+    }
+}
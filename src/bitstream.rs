@@ -0,0 +1,159 @@
+//! GoldSrc 비트 스트림 (MSG_StartBitReading / MSG_ReadBits)
+//!
+//! 바이트 안에서 LSB 부터 읽는다.
+
+use bitvec::{field::BitField, order::Lsb0, slice::BitSlice, vec::BitVec, view::BitView};
+
+pub struct BitReader<'a> {
+    bits: &'a BitSlice<u8, Lsb0>,
+    offset: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        BitReader {
+            bits: bytes.view_bits::<Lsb0>(),
+            offset: 0,
+        }
+    }
+
+    /// 지금까지 읽은 비트 수
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// MSG_EndBitReading 처럼 마지막 바이트를 끝까지 소비한 것으로 본 바이트 수
+    pub fn consumed_bytes(&self) -> usize {
+        self.offset.div_ceil(8)
+    }
+
+    pub fn read_bit(&mut self) -> Option<bool> {
+        let bit = *self.bits.get(self.offset)?;
+        self.offset += 1;
+        Some(bit)
+    }
+
+    /// `n` 비트(최대 32)를 부호 없는 정수로 읽는다.
+    pub fn read_n(&mut self, n: usize) -> Option<u32> {
+        let value = self.peek_n(n)?;
+        self.offset += n;
+        Some(value)
+    }
+
+    pub fn peek_n(&self, n: usize) -> Option<u32> {
+        debug_assert!(n <= 32);
+
+        if n == 0 {
+            return Some(0);
+        }
+
+        let bits = self.bits.get(self.offset..self.offset + n)?;
+        Some(bits.load_le::<u32>())
+    }
+
+    /// 부호 비트 1개 + 크기 (n - 1) 비트
+    pub fn read_signed_n(&mut self, n: usize) -> Option<i32> {
+        let sign = self.read_bit()?;
+        let value = self.read_n(n.saturating_sub(1))? as i32;
+        Some(if sign { -value } else { value })
+    }
+
+    /// 8비트 문자를 널 문자까지 읽는다. 반환값에 널 문자는 포함하지 않는다.
+    pub fn read_string(&mut self) -> Option<Vec<u8>> {
+        let mut string = vec![];
+
+        loop {
+            let c = self.read_n(8)? as u8;
+            if c == 0 {
+                break;
+            }
+            string.push(c);
+        }
+
+        Some(string)
+    }
+
+    pub fn read_bytes(&mut self, n: usize) -> Option<Vec<u8>> {
+        (0..n).map(|_| self.read_n(8).map(|b| b as u8)).collect()
+    }
+
+    /// MSG_ReadBitCoord
+    pub fn read_coord(&mut self) -> Option<f32> {
+        let has_int = self.read_bit()?;
+        let has_fraction = self.read_bit()?;
+
+        if !has_int && !has_fraction {
+            return Some(0.0);
+        }
+
+        let sign = self.read_bit()?;
+        let int_value = if has_int { self.read_n(12)? } else { 0 };
+        let fraction_value = if has_fraction { self.read_n(3)? } else { 0 };
+
+        let value = int_value as f32 + fraction_value as f32 / 8.0;
+        Some(if sign { -value } else { value })
+    }
+
+    /// MSG_ReadBitVec3Coord - 세 축의 존재 플래그를 먼저 읽는다.
+    pub fn read_vec3_coord(&mut self) -> Option<[f32; 3]> {
+        let flags = [self.read_bit()?, self.read_bit()?, self.read_bit()?];
+        let mut coord = [0.0; 3];
+
+        for (value, flag) in coord.iter_mut().zip(flags) {
+            if flag {
+                *value = self.read_coord()?;
+            }
+        }
+
+        Some(coord)
+    }
+}
+
+/// `BitReader` 의 반대 (MSG_StartBitWriting / MSG_WriteBits)
+#[derive(Debug, Default)]
+pub struct BitWriter {
+    bits: BitVec<u8, Lsb0>,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write_bit(&mut self, bit: bool) {
+        self.bits.push(bit);
+    }
+
+    /// `value` 의 아래 `n` 비트(최대 32)를 쓴다.
+    pub fn write_n(&mut self, value: u32, n: usize) {
+        debug_assert!(n <= 32);
+
+        for bit in 0..n {
+            self.bits.push(value >> bit & 1 != 0);
+        }
+    }
+
+    /// 부호 비트 1개 + 크기 (n - 1) 비트
+    pub fn write_signed_n(&mut self, value: i32, n: usize) {
+        self.write_bit(value < 0);
+        self.write_n(value.unsigned_abs(), n.saturating_sub(1));
+    }
+
+    /// 널 문자를 붙여 쓴다.
+    pub fn write_string(&mut self, string: &[u8]) {
+        self.write_bytes(string);
+        self.write_n(0, 8);
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.write_n(b as u32, 8);
+        }
+    }
+
+    /// MSG_EndBitWriting 처럼 마지막 바이트의 남은 비트는 0 으로 채운다.
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        self.bits.set_uninitialized(false);
+        self.bits.into_vec()
+    }
+}
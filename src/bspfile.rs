@@ -1,5 +1,5 @@
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Cursor, Read, Seek, SeekFrom};
 use std::mem::size_of;
 use std::path::Path;
 use byteorder::{LittleEndian, ReadBytesExt};
@@ -132,6 +132,17 @@ pub struct BspData {
 
 pub fn load_bsp_file<P: AsRef<Path>>(path: P) -> std::io::Result<BspData> {
     let mut file = File::open(path)?;
+    read_bsp(&mut file)
+}
+
+/// 메모리에 있는 bsp 파일을 읽는다.
+pub fn parse_bsp(bytes: &[u8]) -> std::io::Result<BspData> {
+    read_bsp(&mut Cursor::new(bytes))
+}
+
+/// 현재 위치가 아니라 스트림 처음을 bsp 파일 시작으로 본다. (lump 오프셋이 파일 기준)
+pub fn read_bsp<R: Read + Seek>(file: &mut R) -> std::io::Result<BspData> {
+    file.seek(SeekFrom::Start(0))?;
 
     let version = file.read_i32::<LittleEndian>()?;
     if version != 30 {
@@ -144,7 +155,7 @@ pub fn load_bsp_file<P: AsRef<Path>>(path: P) -> std::io::Result<BspData> {
         lump.filelen = file.read_i32::<LittleEndian>()?;
     }
 
-    fn read_lump<T: Copy + Default>(file: &mut File, lump: &Lump) -> std::io::Result<Vec<T>> {
+    fn read_lump<T: Copy + Default>(file: &mut (impl Read + Seek), lump: &Lump) -> std::io::Result<Vec<T>> {
         let size = size_of::<T>();
         let count = lump.filelen as usize / size;
         let mut vec = Vec::with_capacity(count);
@@ -167,13 +178,13 @@ pub fn load_bsp_file<P: AsRef<Path>>(path: P) -> std::io::Result<BspData> {
         String::from_utf8_lossy(&buf).trim_end_matches('\0').to_string()
     };
 
-    let planes = read_lump::<DPlane>(&mut file, &lumps[1])?;
-    let texinfo = read_lump::<DTexInfo>(&mut file, &lumps[2])?;
-    let vertexes = read_lump::<DVertex>(&mut file, &lumps[3])?;
-    let clipnodes = read_lump::<DClipNode>(&mut file, &lumps[4])?;
-    let nodes = read_lump::<DNode>(&mut file, &lumps[5])?;
-    let brushes = read_lump::<DBrush>(&mut file, &lumps[6])?;
-    let faces = read_lump::<DFace>(&mut file, &lumps[7])?;
+    let planes = read_lump::<DPlane>(file, &lumps[1])?;
+    let texinfo = read_lump::<DTexInfo>(file, &lumps[2])?;
+    let vertexes = read_lump::<DVertex>(file, &lumps[3])?;
+    let clipnodes = read_lump::<DClipNode>(file, &lumps[4])?;
+    let nodes = read_lump::<DNode>(file, &lumps[5])?;
+    let brushes = read_lump::<DBrush>(file, &lumps[6])?;
+    let faces = read_lump::<DFace>(file, &lumps[7])?;
 
     let lightmaps = {
         file.seek(SeekFrom::Start(lumps[8].fileofs as u64))?;
@@ -182,8 +193,8 @@ pub fn load_bsp_file<P: AsRef<Path>>(path: P) -> std::io::Result<BspData> {
         buf
     };
 
-    let brushsides = read_lump::<DBrushSide>(&mut file, &lumps[9])?;
-    let leafs = read_lump::<DLeaf>(&mut file, &lumps[10])?;
+    let brushsides = read_lump::<DBrushSide>(file, &lumps[9])?;
+    let leafs = read_lump::<DLeaf>(file, &lumps[10])?;
 
     let leaffaces = {
         file.seek(SeekFrom::Start(lumps[11].fileofs as u64))?;
@@ -194,7 +205,7 @@ pub fn load_bsp_file<P: AsRef<Path>>(path: P) -> std::io::Result<BspData> {
             .collect()
     };
 
-    let edges = read_lump::<DEdge>(&mut file, &lumps[12])?;
+    let edges = read_lump::<DEdge>(file, &lumps[12])?;
 
     let surfedges = {
         file.seek(SeekFrom::Start(lumps[13].fileofs as u64))?;
@@ -211,7 +222,7 @@ pub fn load_bsp_file<P: AsRef<Path>>(path: P) -> std::io::Result<BspData> {
             .collect()
     };
 
-    let models = read_lump::<DModel>(&mut file, &lumps[14])?;
+    let models = read_lump::<DModel>(file, &lumps[14])?;
 
     let visdata = {
         file.seek(SeekFrom::Start(lumps[4].fileofs as u64))?;
@@ -0,0 +1,346 @@
+//! 콘솔 커멘드 프레임으로 복원한 입력과 설정(cvar) 타임라인
+//!
+//! 커멘드 프레임은 네트워크 메세지 프레임 사이에 기록되므로 시간은 커멘드 프레임의 것을 그대로 쓴다.
+//! 프레임 N 의 커멘드는 프레임 N+1 의 usercmd 부터 반영되므로 그 프레임에 붙인다.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::demo::{DemoEvent, DemoEventHeader, InputButtons, ParsedDemo};
+
+/// kbutton_t 를 쓰는 +/- 커멘드 이름과 usercmd 버튼
+const KEY_COMMANDS: [(&str, InputButtons); 15] = [
+    ("forward", InputButtons::FORWARD),
+    ("back", InputButtons::BACK),
+    ("moveleft", InputButtons::MOVELEFT),
+    ("moveright", InputButtons::MOVERIGHT),
+    ("left", InputButtons::LEFT),
+    ("right", InputButtons::RIGHT),
+    ("jump", InputButtons::JUMP),
+    ("duck", InputButtons::DUCK),
+    ("speed", InputButtons::RUN),
+    ("use", InputButtons::USE),
+    ("attack", InputButtons::ATTACK),
+    ("attack2", InputButtons::ATTACK2),
+    ("reload", InputButtons::RELOAD),
+    ("alt1", InputButtons::ALT1),
+    ("showscores", InputButtons::SCORE),
+];
+
+/// 키 하나를 누르거나 뗀 순간
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyChange {
+    /// 버튼 하나
+    pub key: InputButtons,
+    pub pressed: bool,
+    /// 커멘드 프레임의 시간
+    pub time: f32,
+    pub frame: i32,
+}
+
+/// 분석 프레임 하나의 키 상태
+#[derive(Debug, Clone, Default)]
+pub struct FrameKeys {
+    /// 이 프레임의 usercmd 를 만들 때 누르고 있던 키
+    pub held: InputButtons,
+    /// 직전 usercmd 이후 바뀐 키. 한 프레임 안에 눌렀다 뗀 키(휠 점프)도 남는다.
+    pub changes: Vec<KeyChange>,
+}
+
+impl FrameKeys {
+    /// 프레임 동안 한 번이라도 눌린 키. usercmd buttons 처럼 눌렀다 뗀 키도 포함한다.
+    pub fn active(&self) -> InputButtons {
+        self.changes
+            .iter()
+            .filter(|change| change.pressed)
+            .fold(self.held, |keys, change| keys | change.key)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KeyTimeline {
+    /// 데모 전체의 키 변화 (LOADING 세그먼트 포함, 파일 순서)
+    pub changes: Vec<KeyChange>,
+    /// `ParsedDemo::frames` 와 인덱스가 같다
+    pub frames: Vec<FrameKeys>,
+}
+
+impl KeyTimeline {
+    pub fn from_demo(demo: &ParsedDemo) -> KeyTimeline {
+        let schedule = CommandSchedule::new(demo);
+        let mut keys = KeyState::default();
+
+        let changes: Vec<Option<KeyChange>> = schedule
+            .commands
+            .iter()
+            .map(|(header, command)| keys.command(command, header.time, header.frame))
+            .collect();
+
+        // 프레임마다 그 usercmd 전에 반영된 변화와 그때의 키 상태
+        let mut held = InputButtons::empty();
+        let frames = schedule
+            .frame_ranges()
+            .map(|range| {
+                let changes: Vec<KeyChange> = changes[range].iter().flatten().copied().collect();
+                for change in &changes {
+                    held.set(change.key, change.pressed);
+                }
+
+                FrameKeys { held, changes }
+            })
+            .collect();
+
+        KeyTimeline {
+            changes: changes.into_iter().flatten().collect(),
+            frames,
+        }
+    }
+
+    /// `frames[index]` 에서 `key` 를 누르고 있으면 누르기 시작한 시간
+    pub fn pressed_since(&self, index: usize, key: InputButtons) -> Option<f32> {
+        if !self.frames.get(index)?.held.contains(key) {
+            return None;
+        }
+
+        self.last_change(index, key, true)
+    }
+
+    /// `frames[index]` 까지 `key` 를 마지막으로 뗀 시간
+    pub fn last_released(&self, index: usize, key: InputButtons) -> Option<f32> {
+        self.last_change(index, key, false)
+    }
+
+    fn last_change(&self, index: usize, key: InputButtons, pressed: bool) -> Option<f32> {
+        self.frames[..=index.min(self.frames.len().checked_sub(1)?)]
+            .iter()
+            .rev()
+            .flat_map(|frame| frame.changes.iter().rev())
+            .find(|change| change.key == key && change.pressed == pressed)
+            .map(|change| change.time)
+    }
+}
+
+/// 따라가는 설정 cvar. 움직임 크기(cl_*speed), 마우스 감도, 프레임 수에 영향을 주는 것들이다.
+pub const TRACKED_CVARS: [&str; 14] = [
+    "fps_max",
+    "cl_forwardspeed",
+    "cl_backspeed",
+    "cl_sidespeed",
+    "cl_upspeed",
+    "cl_movespeedkey",
+    "cl_yawspeed",
+    "cl_pitchspeed",
+    "sensitivity",
+    "m_yaw",
+    "m_pitch",
+    "m_filter",
+    "developer",
+    "ex_interp",
+];
+
+/// 커멘드 프레임의 cvar 대입 하나
+#[derive(Debug, Clone, PartialEq)]
+pub struct CvarChange {
+    /// 소문자 cvar 이름
+    pub name: String,
+    /// 따옴표를 뗀 값
+    pub value: String,
+    pub time: f32,
+    pub frame: i32,
+}
+
+/// 커멘드 프레임으로 따라간 cvar 값
+#[derive(Debug, Clone, Default)]
+pub struct CvarTimeline {
+    /// 데모 전체의 대입 (LOADING 세그먼트 포함, 파일 순서)
+    pub changes: Vec<CvarChange>,
+    /// `ParsedDemo::frames` 인덱스마다 그 usercmd 전까지 반영된 `changes` 수
+    applied: Vec<usize>,
+}
+
+impl CvarTimeline {
+    /// `TRACKED_CVARS` 를 따라간다.
+    pub fn from_demo(demo: &ParsedDemo) -> CvarTimeline {
+        CvarTimeline::with_cvars(demo, &TRACKED_CVARS)
+    }
+
+    pub fn with_cvars(demo: &ParsedDemo, cvars: &[&str]) -> CvarTimeline {
+        let schedule = CommandSchedule::new(demo);
+        let mut timeline = CvarTimeline::default();
+
+        let mut ranges = schedule.frame_ranges().peekable();
+        for (index, (header, command)) in schedule.commands.iter().enumerate() {
+            while ranges.next_if(|range| range.end <= index).is_some() {
+                timeline.applied.push(timeline.changes.len());
+            }
+
+            if let Some((name, value)) = cvar_assignment(command, cvars) {
+                timeline.changes.push(CvarChange {
+                    name,
+                    value,
+                    time: header.time,
+                    frame: header.frame,
+                });
+            }
+        }
+        timeline
+            .applied
+            .extend(ranges.map(|_| timeline.changes.len()));
+
+        timeline
+    }
+
+    /// `frames[index]` 시점의 값. 데모 안에서 대입한 적이 없으면 None 이다 (기본값은 데모에 없다).
+    pub fn value(&self, index: usize, name: &str) -> Option<&str> {
+        let applied = *self.applied.get(index)?;
+
+        self.changes[..applied]
+            .iter()
+            .rev()
+            .find(|change| change.name.eq_ignore_ascii_case(name))
+            .map(|change| change.value.as_str())
+    }
+
+    /// 숫자로 읽을 수 있는 값
+    pub fn value_f32(&self, index: usize, name: &str) -> Option<f32> {
+        self.value(index, name)?.parse().ok()
+    }
+
+    /// `frames[index]` 시점에 값이 알려진 모든 cvar
+    pub fn values(&self, index: usize) -> HashMap<&str, &str> {
+        let applied = self.applied.get(index).copied().unwrap_or_default();
+
+        self.changes[..applied]
+            .iter()
+            .map(|change| (change.name.as_str(), change.value.as_str()))
+            .collect()
+    }
+}
+
+/// `name value` 형식의 대입이면 소문자 이름과 따옴표를 뗀 값.
+/// 값 없이 이름만 쓰면 현재 값을 출력하는 것이므로 대입이 아니다.
+fn cvar_assignment(command: &str, cvars: &[&str]) -> Option<(String, String)> {
+    let (name, value) = command.trim().split_once(char::is_whitespace)?;
+    let name = cvars.iter().find(|cvar| cvar.eq_ignore_ascii_case(name))?;
+
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .map_or(value, |value| value.split('"').next().unwrap_or_default());
+    if value.is_empty() {
+        return None;
+    }
+
+    Some((name.to_ascii_lowercase(), value.to_string()))
+}
+
+/// 파일 순서의 모든 커멘드와, 분석 프레임마다 그 usercmd 전까지 반영된 커멘드 수
+struct CommandSchedule<'a> {
+    commands: Vec<(DemoEventHeader, &'a str)>,
+    /// `ParsedDemo::frames` 와 인덱스가 같다
+    applied: Vec<usize>,
+}
+
+impl<'a> CommandSchedule<'a> {
+    fn new(demo: &'a ParsedDemo) -> Self {
+        let mut commands: Vec<(DemoEventHeader, &str)> = vec![];
+        let mut applied = vec![];
+        // 반영된 커멘드 수
+        let mut done = 0;
+
+        for event in &demo.events {
+            match event {
+                DemoEvent::ConsoleCommand(header, command) => commands.push((*header, command)),
+                DemoEvent::NetworkMessage(header, frame) => {
+                    // 커멘드는 다음 프레임의 usercmd 부터 반영된다
+                    done += commands[done..]
+                        .iter()
+                        .take_while(|(command_header, _)| command_header.frame < header.frame)
+                        .count();
+
+                    if frame.playback().is_some() {
+                        applied.push(done);
+                    }
+                }
+                // 세그먼트가 바뀌면 프레임 번호가 다시 시작한다
+                DemoEvent::NextSection(_) => done = commands.len(),
+                _ => {}
+            }
+        }
+
+        CommandSchedule { commands, applied }
+    }
+
+    /// 프레임마다 직전 프레임 이후에 반영된 커멘드 범위
+    fn frame_ranges(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        let starts = std::iter::once(0).chain(self.applied.iter().copied());
+
+        starts.zip(&self.applied).map(|(start, &end)| start..end)
+    }
+}
+
+/// 클라이언트의 kbutton_t 흉내. 같은 버튼에 키 두 개까지 묶을 수 있어서
+/// 두 키를 모두 떼야 버튼이 떨어진다.
+#[derive(Debug, Default)]
+struct KeyState {
+    held: InputButtons,
+    /// 버튼을 누르고 있는 키 번호. 콘솔에서 직접 입력하면 -1 이다.
+    down: HashMap<InputButtons, [Option<i32>; 2]>,
+}
+
+impl KeyState {
+    /// IN_KeyDown / IN_KeyUp. 버튼 상태가 바뀌었으면 그 변화를 돌려준다.
+    fn command(&mut self, command: &str, time: f32, frame: i32) -> Option<KeyChange> {
+        let mut args = command.split_whitespace();
+        let name = args.next()?;
+        let (pressed, name) = match name.split_at_checked(1)? {
+            ("+", name) => (true, name),
+            ("-", name) => (false, name),
+            _ => return None,
+        };
+        let (_, key) = *KEY_COMMANDS
+            .iter()
+            .find(|(command, _)| command.eq_ignore_ascii_case(name))?;
+        let key_number = args.next().and_then(|arg| arg.parse::<i32>().ok());
+
+        let down = self.down.entry(key).or_default();
+        if pressed {
+            let key_number = Some(key_number.unwrap_or(-1));
+            // 키 반복 입력이거나 세 번째 키
+            if down.contains(&key_number) {
+                return None;
+            }
+            let slot = down.iter_mut().find(|slot| slot.is_none())?;
+            *slot = key_number;
+
+            if self.held.contains(key) {
+                return None;
+            }
+            self.held.insert(key);
+        } else {
+            match key_number {
+                // 콘솔에서 직접 뗐으면 모든 키를 뗀 것으로 본다
+                None => *down = [None, None],
+                Some(key_number) => {
+                    let slot = down.iter_mut().find(|slot| **slot == Some(key_number))?;
+                    *slot = None;
+                    if down.iter().any(Option::is_some) {
+                        return None;
+                    }
+                }
+            }
+
+            if !self.held.contains(key) {
+                return None;
+            }
+            self.held.remove(key);
+        }
+
+        Some(KeyChange {
+            key,
+            pressed,
+            time,
+            frame,
+        })
+    }
+}
@@ -0,0 +1,193 @@
+//! 델타 압축 디코더 (DELTA_ParseDelta)
+//!
+//! 서버는 svc_deltadescription 으로 구조체별 필드 인코딩 방식을 먼저 보내고,
+//! 이후 메세지에서는 바뀐 필드만 비트마스크와 함께 보낸다.
+
+use crate::{
+    bitstream::{BitReader, BitWriter},
+    types::*,
+};
+
+/// svc_deltadescription 자체를 해석하기 위한 디코더. 엔진에 내장되어 있어 전송되지 않는다.
+pub fn delta_description_decoder() -> DeltaDecoder {
+    let field = |name: &str, flags, bits, pre_multiplier| DeltaDecoderField {
+        name: name.as_bytes().to_vec(),
+        flags,
+        offset: 0,
+        size: 0,
+        bits,
+        pre_multiplier,
+        post_multiplier: 1.0,
+    };
+
+    vec![
+        field("fieldType", DT_INTEGER, 32, 1.0),
+        field("fieldName", DT_STRING, 1, 1.0),
+        field("fieldOffset", DT_INTEGER, 16, 1.0),
+        field("fieldSize", DT_INTEGER, 8, 1.0),
+        field("significant_bits", DT_INTEGER, 8, 1.0),
+        field("premultiply", DT_FLOAT, 32, 4000.0),
+        field("postmultiply", DT_FLOAT, 32, 4000.0),
+    ]
+}
+
+/// delta_description_t 델타 하나를 필드 정의로 바꾼다.
+/// 엔진은 0 으로 채운 구조체 위에 델타를 적용하지만 배율은 1.0 이 기본값이어야 쓸모가 있다.
+pub fn decoder_field_from_delta(delta: &Delta) -> DeltaDecoderField {
+    let number = |key: &str| delta.get(key).and_then(DeltaValue::as_f32);
+
+    DeltaDecoderField {
+        name: match delta.get("fieldName") {
+            Some(DeltaValue::String(name)) => name.clone(),
+            _ => vec![],
+        },
+        flags: delta.get("fieldType").map_or(0, |value| match value {
+            DeltaValue::Number { raw, .. } => *raw as u32,
+            DeltaValue::String(_) => 0,
+        }),
+        offset: number("fieldOffset").unwrap_or(0.0) as u16,
+        size: number("fieldSize").unwrap_or(0.0) as u8,
+        bits: number("significant_bits").unwrap_or(0.0) as u8,
+        pre_multiplier: number("premultiply").unwrap_or(1.0),
+        post_multiplier: number("postmultiply").unwrap_or(1.0),
+    }
+}
+
+/// 3비트 바이트 수 + 비트마스크 다음에 마스크에 켜진 필드만 순서대로 읽는다.
+pub fn parse_delta(br: &mut BitReader, decoder: &[DeltaDecoderField]) -> Option<Delta> {
+    let mask_bytes = br.read_n(3)? as usize;
+    let mask = br.read_bytes(mask_bytes)?;
+    let mut delta = Delta::new();
+
+    for (index, field) in decoder.iter().enumerate() {
+        let Some(byte) = mask.get(index / 8) else {
+            break;
+        };
+
+        if byte & (1 << (index % 8)) == 0 {
+            continue;
+        }
+
+        delta.insert(field.name(), parse_field(br, field)?);
+    }
+
+    Some(delta)
+}
+
+fn parse_field(br: &mut BitReader, field: &DeltaDecoderField) -> Option<DeltaValue> {
+    let signed = field.flags & DT_SIGNED != 0;
+    let bits = field.bits as usize;
+    let pre = field.pre_multiplier as f64;
+    let post = field.post_multiplier as f64;
+
+    let value = match field.flags & !DT_SIGNED {
+        // 정수 타입도 엔진(DELTA_ParseDelta)은 premultiplier 로 나누고 postmultiplier 를 곱한다
+        DT_BYTE | DT_SHORT | DT_FLOAT | DT_INTEGER => {
+            let raw = read_int(br, bits, signed)?;
+            DeltaValue::Number {
+                raw,
+                value: raw as f64 / pre * post,
+            }
+        }
+        DT_ANGLE => {
+            let raw = br.read_n(bits)? as i64;
+            DeltaValue::Number {
+                raw,
+                value: raw as f64 * 360.0 / (1u64 << bits) as f64,
+            }
+        }
+        // 시간 창은 항상 부호 있는 값이다. 현재 시각 기준 상대값을 그대로 둔다.
+        DT_TIMEWINDOW_8 => {
+            let raw = br.read_signed_n(8)? as i64;
+            DeltaValue::Number {
+                raw,
+                value: raw as f64 / 100.0,
+            }
+        }
+        DT_TIMEWINDOW_BIG => {
+            let raw = br.read_signed_n(bits)? as i64;
+            DeltaValue::Number {
+                raw,
+                value: raw as f64 / pre,
+            }
+        }
+        DT_STRING => DeltaValue::String(br.read_string()?),
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+fn read_int(br: &mut BitReader, bits: usize, signed: bool) -> Option<i64> {
+    if signed {
+        br.read_signed_n(bits).map(i64::from)
+    } else {
+        br.read_n(bits).map(i64::from)
+    }
+}
+
+/// `parse_delta` 의 반대 (DELTA_WriteDelta). 디코더에 없거나 타입이 맞지 않는 필드는 빠진다.
+/// 값은 비트에서 읽은 `raw` 를 그대로 쓰므로 읽은 델타를 다시 쓰면 같은 비트가 나온다.
+pub fn write_delta(bw: &mut BitWriter, decoder: &[DeltaDecoderField], delta: &Delta) {
+    let fields: Vec<(usize, &DeltaDecoderField, &DeltaValue)> = decoder
+        .iter()
+        .enumerate()
+        .filter_map(|(index, field)| {
+            let value = delta.get(&field.name())?;
+            let is_string = field.flags & !DT_SIGNED == DT_STRING;
+            (is_string == matches!(value, DeltaValue::String(_))).then_some((index, field, value))
+        })
+        .collect();
+
+    let mut mask = vec![0u8; fields.last().map_or(0, |(index, _, _)| index / 8 + 1)];
+    for (index, _, _) in &fields {
+        mask[index / 8] |= 1 << (index % 8);
+    }
+
+    bw.write_n(mask.len() as u32, 3);
+    bw.write_bytes(&mask);
+
+    for (_, field, value) in fields {
+        write_field(bw, field, value);
+    }
+}
+
+fn write_field(bw: &mut BitWriter, field: &DeltaDecoderField, value: &DeltaValue) {
+    let signed = field.flags & DT_SIGNED != 0;
+    let bits = field.bits as usize;
+
+    let raw = match value {
+        DeltaValue::Number { raw, .. } => *raw,
+        DeltaValue::String(string) => return bw.write_string(string),
+    };
+
+    match field.flags & !DT_SIGNED {
+        DT_BYTE | DT_SHORT | DT_FLOAT | DT_INTEGER if signed => bw.write_signed_n(raw as i32, bits),
+        DT_TIMEWINDOW_8 => bw.write_signed_n(raw as i32, 8),
+        DT_TIMEWINDOW_BIG => bw.write_signed_n(raw as i32, bits),
+        _ => bw.write_n(raw as u32, bits),
+    }
+}
+
+/// `from` 에서 `to` 로 바뀐 필드만 모은 델타. 어느 한쪽에 없는 필드는 0 으로 본다.
+pub fn diff_delta(decoder: &[DeltaDecoderField], from: &Delta, to: &Delta) -> Delta {
+    decoder
+        .iter()
+        .filter_map(|field| {
+            let name = field.name();
+            let zero = if field.flags & !DT_SIGNED == DT_STRING {
+                DeltaValue::String(vec![])
+            } else {
+                DeltaValue::Number { raw: 0, value: 0.0 }
+            };
+
+            let value = to.get(&name).unwrap_or(&zero);
+            (value != from.get(&name).unwrap_or(&zero)).then(|| (name, value.clone()))
+        })
+        .collect()
+}
+
+/// 이전 상태 위에 델타를 덮어써 새 상태를 만든다.
+pub fn apply_delta(state: &mut Delta, delta: &Delta) {
+    state.extend(delta.iter().map(|(name, value)| (name.clone(), value.clone())));
+}
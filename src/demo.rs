@@ -1,7 +1,22 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, Read, Seek, SeekFrom};
-use byteorder::{LittleEndian, ReadBytesExt};
+use std::io::{self, Read};
+use std::ops::Range;
+
+use crate::entity::EntityTracker;
+use crate::error::{DemoParseError, Recovery};
+use crate::parse::{
+    ErrorMode, MsgDataParseMode, check_protocol, parse_demo_checked, parse_directory_checked,
+    parse_header_checked,
+};
+use crate::player::{PlayerTrack, PlayerTracker, entity_demo_frames};
+use crate::protocol::GameMod;
+use crate::types::{
+    self, ClientData, EngineMessage, Event, FrameData, MessageData, MoveVars, NetMessage,
+    NetworkMessage, RefParams, Resource, Sound, UserCmd, WeaponAnimation, bytes_to_string,
+};
+use crate::usermsg::CsUserMessage;
+use crate::weapon::{Weapon, WeaponTracker};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Vector3 {
@@ -12,15 +27,15 @@ pub struct Vector3 {
 
 impl Vector3 {
     pub fn dot(&self, other: Vector3) -> f32 {
-        return self.x * other.x + self.y * other.y + self.z * other.z
+        self.x * other.x + self.y * other.y + self.z * other.z
     }
 
     pub fn length(&self) -> f32 {
-        return (self.x * self.x + self.y * self.y).sqrt()
+        (self.x * self.x + self.y * self.y).sqrt()
     }
 
     pub fn length_3d(&self) -> f32{
-        return (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
     }
 
     pub fn normalize(&self) -> Vector3 {
@@ -29,9 +44,47 @@ impl Vector3 {
             return Vector3 {x:0.0, y: 0.0, z: 0.0};
         }
 
-        let normalized = Vector3 { x: (self.x / length), y: (self.y / length), z: (self.z / length) };
+        Vector3 { x: (self.x / length), y: (self.y / length), z: (self.z / length) }
+    }
+}
+
+impl From<[f32; 3]> for Vector3 {
+    fn from(v: [f32; 3]) -> Self {
+        Vector3 { x: v[0], y: v[1], z: v[2] }
+    }
+}
 
-        return normalized
+impl From<Vector3> for [f32; 3] {
+    fn from(v: Vector3) -> Self {
+        [v.x, v.y, v.z]
+    }
+}
+
+bitflags::bitflags! {
+    /// usercmd_t.buttons 의 IN_* 비트.
+    /// 한 프레임 안에 눌렀다 뗀 입력(휠 점프, 스크립트)도 그 프레임에는 켜져 있다.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct InputButtons: u16 {
+        const ATTACK = 1 << 0;
+        const JUMP = 1 << 1;
+        const DUCK = 1 << 2;
+        const FORWARD = 1 << 3;
+        const BACK = 1 << 4;
+        const USE = 1 << 5;
+        const CANCEL = 1 << 6;
+        /// +left (키보드 회전)
+        const LEFT = 1 << 7;
+        /// +right (키보드 회전)
+        const RIGHT = 1 << 8;
+        const MOVELEFT = 1 << 9;
+        const MOVERIGHT = 1 << 10;
+        const ATTACK2 = 1 << 11;
+        /// +speed (걷기)
+        const RUN = 1 << 12;
+        const RELOAD = 1 << 13;
+        const ALT1 = 1 << 14;
+        /// +showscores
+        const SCORE = 1 << 15;
     }
 }
 
@@ -57,344 +110,810 @@ pub struct DemoFrame {
     pub forwardmove: f32,
     pub sidemove: f32,
     pub upmove: f32,
+    pub buttons: InputButtons,
+    /// 입력이 기록되지 않은 다른 플레이어 프레임에서 움직임으로 추정한 JUMP / DUCK.
+    /// 실제 usercmd 가 있는 프레임에서는 항상 비어 있다.
+    pub estimated_buttons: InputButtons,
+    /// usercmd 의 impulse (100 손전등, 201 스프레이 등)
+    pub impulse: u8,
+    /// 이 프레임에 고른 무기 번호. 고르지 않았으면 0
+    pub weaponselect: u8,
     pub forward: Vector3,
     pub right: Vector3,
     pub up: Vector3,
+    /// HLTV 관전 중 녹화된 프레임 (simorg 는 카메라 위치다)
+    pub spectator: bool,
+    /// 시점이 붙어 있는 엔티티 번호. 1인칭 관전이면 관전 대상 플레이어다.
+    pub viewentity: i32,
+    /// 위 필드들을 뽑아 온 원본 구조체. health, waterlevel, punchangle 등은 여기서 읽는다.
+    pub ref_params: RefParams,
+    pub user_cmd: UserCmd,
+    /// 서버 물리 설정 (sv_stopspeed, sv_maxspeed, sv_stepsize 등)
+    pub movevars: MoveVars,
+    /// 녹화한 플레이어가 들고 있는 무기. CS 계열이 아니거나 알 수 없으면 None
+    pub current_weapon: Option<Weapon>,
 }
 
+/// 데모 헤더 정보
+#[derive(Debug, Clone)]
+pub struct DemoHeader {
+    pub demo_version: i32,
+    pub network_version: i32,
+    pub map_name: String,
+    pub game_dll: String,
+    pub crc: u32,
+    pub dir_offset: i32,
+}
 
-pub fn parse(path: &str) -> io::Result<Vec<DemoFrame>> {
-    
-    fn read_fixed_string<R: Read>(reader: &mut R, size: usize) -> io::Result<String> {
-        let mut buf = vec![0u8; size];
-        reader.read_exact(&mut buf)?;
-        let string = buf.split(|&b| b == 0)
-                        .next()
-                        .unwrap_or(&[])
-                        .iter()
-                        .map(|&c| c as char)
-                        .collect::<String>();
-        Ok(string)
+impl From<&types::Header> for DemoHeader {
+    fn from(header: &types::Header) -> Self {
+        DemoHeader {
+            demo_version: header.demo_protocol,
+            network_version: header.network_protocol,
+            map_name: header.map_name(),
+            game_dll: header.game_directory(),
+            crc: header.map_checksum,
+            dir_offset: header.directory_offset,
+        }
     }
+}
 
-    let mut file = File::open(path)?;
+impl DemoHeader {
+    /// 게임 디렉토리로 구분한 모드
+    pub fn game_mod(&self) -> GameMod {
+        GameMod::from_game_dir(&self.game_dll)
+    }
+}
 
-    // HLDEMO 매직스트링
-    let mut magic = [0u8; 8];
-    file.read_exact(&mut magic)?;
-    if &magic[..6] != b"HLDEMO" {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid Demo Header"));
-    }
-
-    let _demo_version = file.read_i32::<LittleEndian>()?;
-    if _demo_version != 5 {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a cs 1.6 Demo"));
-    }
-
-    let _network_version = file.read_i32::<LittleEndian>()?;
-    let _map_name = read_fixed_string(&mut file, 260)?;
-    let _game_dll = read_fixed_string(&mut file, 260)?;
-    let _crc = file.read_i32::<LittleEndian>()?;
-    let dir_offset = file.read_i32::<LittleEndian>()?;
-
-    // 오프셋으로 이동 - 데모 마지막 부분
-    file.seek(SeekFrom::Start(dir_offset as u64))?;
-    let entry_count = file.read_i32::<LittleEndian>()?;
-
-    let mut entry_offsets = Vec::new();
-    for _i in 0..entry_count {
-        let _entry_type = file.read_i32::<LittleEndian>()?;
-        let _title = read_fixed_string(&mut file, 64)?;
-        let _flags = file.read_i32::<LittleEndian>()?;
-        let _play = file.read_i32::<LittleEndian>()?;
-        let _time = file.read_f32::<LittleEndian>()?;
-        let _frames = file.read_i32::<LittleEndian>()?;
-        let offset = file.read_i32::<LittleEndian>()?;
-        let _length = file.read_i32::<LittleEndian>()?;
-        entry_offsets.push(offset);
-    }
-
-    // 실제 게임 데이터 부로 포인터 이동
-    let offset = entry_offsets[1] as u64;
-    file.seek(SeekFrom::Start(offset))?;
-    
-    let mut frames: Vec<DemoFrame> = Vec::new();
+/// 디렉토리 엔트리 (세그먼트 정보)
+#[derive(Debug, Clone)]
+pub struct DirectoryEntry {
+    pub entry_type: i32,
+    pub title: String,
+    pub flags: i32,
+    pub cd_track: i32,
+    /// 세그먼트 길이(초)
+    pub time: f32,
+    pub frame_count: i32,
+    pub offset: i32,
+    pub length: i32,
+}
 
-    let mut commands_by_frame: HashMap<i32, Vec<String>> = HashMap::new();
-    
-    loop {
-        let demo_type = match file.read_u8() {
-            Ok(v) => v,
-            Err(_) => break,
-        };
+impl From<&types::DirectoryEntry> for DirectoryEntry {
+    fn from(entry: &types::DirectoryEntry) -> Self {
+        DirectoryEntry {
+            entry_type: entry.type_,
+            title: entry.description(),
+            flags: entry.flags,
+            cd_track: entry.cd_track,
+            time: entry.track_time,
+            frame_count: entry.frame_count,
+            offset: entry.offset,
+            length: entry.file_length,
+        }
+    }
+}
 
-        let time = file.read_f32::<LittleEndian>()?;
-        let frame = file.read_i32::<LittleEndian>()?;
+/// 디렉토리 타입 - LOADING 세그먼트
+pub const DIRECTORY_ENTRY_LOADING: i32 = 0;
 
-        //네트워크 메세지 - 실제 서버 통신내용
-        if demo_type == 1 {
-            let _timestamp = file.read_f32::<LittleEndian>()?;
+/// 디렉토리 엔트리 하나에 해당하는 프레임 묶음
+#[derive(Debug, Clone)]
+pub struct DemoSegment {
+    pub entry_type: i32,
+    pub title: String,
+    /// 이 세그먼트의 프레임 인덱스 범위. LOADING 세그먼트는 `ParsedDemo::loading_frames`,
+    /// 그 밖의 세그먼트는 `ParsedDemo::frames` 의 범위다.
+    pub frames: Range<usize>,
+}
 
-            let vieworg = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+impl DemoSegment {
+    pub fn is_loading(&self) -> bool {
+        self.entry_type == DIRECTORY_ENTRY_LOADING
+    }
+}
 
-            let viewangle = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+/// 모든 이벤트가 공유하는 프레임 헤더
+#[derive(Debug, Clone, Copy)]
+pub struct DemoEventHeader {
+    /// `ParsedDemo::segments` 인덱스
+    pub segment: usize,
+    pub time: f32,
+    pub frame: i32,
+}
 
-            let forward = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+/// 프레임 타입별 이벤트
+///
+/// `DemoReader` 는 네트워크 메세지 프레임의 DemoFrame 을 이벤트에 담아 돌려주고,
+/// `ParsedDemo::events` 는 프레임을 `ParsedDemo::frames` 에 한 번만 두고 인덱스로 가리킨다 (`IndexedEvent`).
+#[derive(Debug, Clone)]
+pub enum DemoEvent<F = Box<DemoFrame>> {
+    /// 0, 1 - 네트워크 메세지 프레임의 움직임 데이터
+    NetworkMessage(DemoEventHeader, F),
+    /// 2
+    DemoStart(DemoEventHeader),
+    /// 3
+    ConsoleCommand(DemoEventHeader, String),
+    /// 4
+    ClientData(DemoEventHeader, ClientData),
+    /// 5
+    NextSection(DemoEventHeader),
+    /// 6
+    Event(DemoEventHeader, Event),
+    /// 7
+    WeaponAnimation(DemoEventHeader, WeaponAnimation),
+    /// 8
+    Sound(DemoEventHeader, Sound),
+    /// 9
+    DemoBuffer(DemoEventHeader, Vec<u8>),
+    /// 네트워크 메세지 프레임 안의 사용자 메세지. 해당 NetworkMessage 이벤트 바로 뒤에 온다.
+    UserMessage(DemoEventHeader, CsUserMessage),
+}
 
-            let right = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+/// `ParsedDemo` 에 한 번만 저장된 네트워크 메세지 프레임의 위치
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameIndex {
+    /// `ParsedDemo::loading_frames` 인덱스
+    Loading(usize),
+    /// `ParsedDemo::frames` 인덱스
+    Playback(usize),
+}
 
-            let up = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+impl FrameIndex {
+    /// 분석용 `ParsedDemo::frames` 인덱스. LOADING 프레임이면 None
+    pub fn playback(self) -> Option<usize> {
+        match self {
+            FrameIndex::Playback(index) => Some(index),
+            FrameIndex::Loading(_) => None,
+        }
+    }
+}
 
-            let frametime = file.read_f32::<LittleEndian>()?;
-            let _time = file.read_f32::<LittleEndian>()?;
-            let _intermission = file.read_i32::<LittleEndian>()?;
-            let _puased = file.read_i32::<LittleEndian>()?;
-            let _spectator = file.read_i32::<LittleEndian>()?;
-            let onground = file.read_i32::<LittleEndian>()? != 0;
-            
-            let _waterlevel = file.read_i32::<LittleEndian>()?;
-
-            let simvel = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+/// `ParsedDemo::events` 의 이벤트
+pub type IndexedEvent = DemoEvent<FrameIndex>;
+
+impl<F> DemoEvent<F> {
+    pub fn header(&self) -> &DemoEventHeader {
+        match self {
+            DemoEvent::NetworkMessage(header, _)
+            | DemoEvent::DemoStart(header)
+            | DemoEvent::ConsoleCommand(header, _)
+            | DemoEvent::ClientData(header, _)
+            | DemoEvent::NextSection(header)
+            | DemoEvent::Event(header, _)
+            | DemoEvent::WeaponAnimation(header, _)
+            | DemoEvent::Sound(header, _)
+            | DemoEvent::DemoBuffer(header, _)
+            | DemoEvent::UserMessage(header, _) => header,
+        }
+    }
 
-            let simorg = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+    pub fn time(&self) -> f32 {
+        self.header().time
+    }
 
-            let viewheight = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+    pub fn frame(&self) -> i32 {
+        self.header().frame
+    }
 
-            let _idealpitch = file.read_f32::<LittleEndian>()?;
+    /// NetworkMessage 이벤트의 프레임만 `f` 로 바꾼다.
+    pub fn map_frame<G>(self, f: impl FnOnce(F) -> G) -> DemoEvent<G> {
+        match self {
+            DemoEvent::NetworkMessage(header, frame) => DemoEvent::NetworkMessage(header, f(frame)),
+            DemoEvent::DemoStart(header) => DemoEvent::DemoStart(header),
+            DemoEvent::ConsoleCommand(header, command) => {
+                DemoEvent::ConsoleCommand(header, command)
+            }
+            DemoEvent::ClientData(header, client_data) => {
+                DemoEvent::ClientData(header, client_data)
+            }
+            DemoEvent::NextSection(header) => DemoEvent::NextSection(header),
+            DemoEvent::Event(header, event) => DemoEvent::Event(header, event),
+            DemoEvent::WeaponAnimation(header, animation) => {
+                DemoEvent::WeaponAnimation(header, animation)
+            }
+            DemoEvent::Sound(header, sound) => DemoEvent::Sound(header, sound),
+            DemoEvent::DemoBuffer(header, buffer) => DemoEvent::DemoBuffer(header, buffer),
+            DemoEvent::UserMessage(header, message) => DemoEvent::UserMessage(header, message),
+        }
+    }
+}
 
-            let _clviewangles = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
-            
-            let _health = file.read_f32::<LittleEndian>()?;
+/// 파싱된 데모 전체
+#[derive(Debug, Clone)]
+pub struct ParsedDemo {
+    pub header: DemoHeader,
+    pub directory: Vec<DirectoryEntry>,
+    /// 디렉토리 순서대로의 모든 세그먼트
+    pub segments: Vec<DemoSegment>,
+    /// LOADING 을 제외한 세그먼트의 프레임을 이어붙인 것 (분석용)
+    pub frames: Vec<DemoFrame>,
+    /// LOADING 세그먼트의 프레임. 접속 중이라 움직임 데이터는 의미가 없다.
+    pub loading_frames: Vec<DemoFrame>,
+    /// 모든 세그먼트의 프레임을 파일 순서대로 담은 이벤트
+    pub events: Vec<IndexedEvent>,
+    /// 한 번이라도 등장한 슬롯별 플레이어 상태. `states` 는 `frames` 와 인덱스가 같다.
+    pub players: Vec<PlayerTrack>,
+    /// svc_serverinfo 의 서버 이름
+    pub hostname: String,
+    /// svc_resourcelist 로 받은 리소스 (이벤트 스크립트, 사운드, 모델 등)
+    pub resources: Vec<Resource>,
+    /// 깨진 프레임을 건너뛰었거나 디렉토리를 다시 만든 경우의 기록. 엄격 모드에서는 항상 비어 있다.
+    pub recovery: Recovery,
+}
 
-            let _crosshairangle = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+impl ParsedDemo {
+    /// 디렉토리에 기록된 데모 길이(초)
+    pub fn duration(&self) -> f32 {
+        self.directory.iter().map(|entry| entry.time).sum()
+    }
 
-            let _viewsize = file.read_i32::<LittleEndian>()?;
+    /// 세그먼트의 프레임
+    pub fn segment_frames(&self, segment: &DemoSegment) -> &[DemoFrame] {
+        if segment.is_loading() {
+            &self.loading_frames[segment.frames.clone()]
+        } else {
+            &self.frames[segment.frames.clone()]
+        }
+    }
 
-            let _punchangle = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+    /// NetworkMessage 이벤트가 가리키는 프레임
+    pub fn event_frame(&self, event: &IndexedEvent) -> Option<&DemoFrame> {
+        match event {
+            DemoEvent::NetworkMessage(_, FrameIndex::Loading(index)) => {
+                self.loading_frames.get(*index)
+            }
+            DemoEvent::NetworkMessage(_, FrameIndex::Playback(index)) => self.frames.get(*index),
+            _ => None,
+        }
+    }
 
-            let _maxclients = file.read_i32::<LittleEndian>()?;
-            let _viewentity = file.read_i32::<LittleEndian>()?;
-            let _playernum = file.read_i32::<LittleEndian>()?;
-            let _maxentities = file.read_i32::<LittleEndian>()?;
-            let _demoplayback = file.read_i32::<LittleEndian>()?;
-            let _hardware = file.read_i32::<LittleEndian>()?;
-            let _smoothing = file.read_i32::<LittleEndian>()?;
-            let _ptrcmd = file.read_i32::<LittleEndian>()?;
-            let _ptrmovevars = file.read_i32::<LittleEndian>()?;
-            let _viewport_x = file.read_i32::<LittleEndian>()?;
-            let _viewport_y = file.read_i32::<LittleEndian>()?;
-            let _resolutionwidth = file.read_i32::<LittleEndian>()?;
-            let _resolutionheight = file.read_i32::<LittleEndian>()?;
-            let _nextview = file.read_i32::<LittleEndian>()?;
-            let _onlyclientdraw = file.read_i32::<LittleEndian>()?;
-            let _lerpmsec = file.read_u16::<LittleEndian>()?;
-            let msec = file.read_u8()?;
-            let _align1 = file.read_u8()?;
-            
-            let _viewangles2 = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
+    /// LOADING 세그먼트를 제외한 이벤트와 그 이벤트 뒤에 오는 `frames` 인덱스.
+    /// NetworkMessage 이벤트는 자기 프레임의 인덱스이고, 마지막 프레임 뒤의 이벤트는 `frames.len()` 이다.
+    pub fn playback_events(&self) -> impl Iterator<Item = (usize, &IndexedEvent)> {
+        let mut next = 0;
+
+        self.events.iter().filter_map(move |event| {
+            if self.segments[event.header().segment].is_loading() {
+                return None;
+            }
+
+            let index = match event {
+                DemoEvent::NetworkMessage(_, FrameIndex::Playback(index)) => {
+                    next = index + 1;
+                    *index
+                }
+                _ => next,
             };
 
-            let forwardmove = file.read_f32::<LittleEndian>()?;
-            let sidemove = file.read_f32::<LittleEndian>()?;
-            let upmove = file.read_f32::<LittleEndian>()?;
-            let _lightlevel = file.read_u8()?;
-            let _align2 = file.read_u8()?;
-            let _buttons = file.read_u16::<LittleEndian>()?;
-            let _impulse = file.read_u8()?;
-            let _weaponselect = file.read_u8()?;
-            let _align3 = file.read_u8()?;
-            let _align4 = file.read_u8()?;
-            let _impactindex = file.read_i32::<LittleEndian>()?;
-            
-            let _impactposition = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+            Some((index, event))
+        })
+    }
 
-            let gravity = file.read_f32::<LittleEndian>()?;
-            let _stopspeed = file.read_f32::<LittleEndian>()?;
-            let _maxspeed = file.read_f32::<LittleEndian>()?;
-            let _spectatormaxspeed = file.read_f32::<LittleEndian>()?;
-            let accelerate = file.read_f32::<LittleEndian>()?;
-            let airaccelerate = file.read_f32::<LittleEndian>()?;
-            let _wateraccelerate = file.read_f32::<LittleEndian>()?;
-            let friction = file.read_f32::<LittleEndian>()?;
-            let edgefriction = file.read_f32::<LittleEndian>()?;
-            let _waterfriction = file.read_f32::<LittleEndian>()?;
-            let _entgravity = file.read_f32::<LittleEndian>()?;
-            let _bounce = file.read_f32::<LittleEndian>()?;
-            let _stepsize = file.read_f32::<LittleEndian>()?;
-            let maxvelocitiy = file.read_f32::<LittleEndian>()?;
-            let _zmax = file.read_f32::<LittleEndian>()?;
-            let _waveheight = file.read_f32::<LittleEndian>()?;
-            let _footsteps = file.read_i32::<LittleEndian>()?;
-            let _skyname = read_fixed_string(&mut file, 32)?;
-            let _rollangle = file.read_f32::<LittleEndian>()?;
-            let _rollspeed = file.read_f32::<LittleEndian>()?;
-            let _skycolor_r = file.read_f32::<LittleEndian>()?;
-            let _skycolor_g = file.read_f32::<LittleEndian>()?;
-            let _skycolor_b = file.read_f32::<LittleEndian>()?;
-            
-            let _sky_vector = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+    /// 모든 사용자 메세지를 시간순으로
+    pub fn user_messages(&self) -> impl Iterator<Item = (&DemoEventHeader, &CsUserMessage)> {
+        self.events.iter().filter_map(|event| match event {
+            DemoEvent::UserMessage(header, message) => Some((header, message)),
+            _ => None,
+        })
+    }
 
-            let _view = Vector3 {
-                x: file.read_f32::<LittleEndian>()?,
-                y: file.read_f32::<LittleEndian>()?,
-                z: file.read_f32::<LittleEndian>()?,
-            };
+    /// 녹화한 플레이어의 트랙
+    pub fn pov_player(&self) -> Option<&PlayerTrack> {
+        self.players.iter().find(|player| player.is_pov)
+    }
+
+    pub fn player(&self, slot: u8) -> Option<&PlayerTrack> {
+        self.players.iter().find(|player| player.slot == slot)
+    }
 
-            let _viewmodel = file.read_i32::<LittleEndian>()?;
-            let _incoming_sequence = file.read_i32::<LittleEndian>()?;
-            let _incoming_ack = file.read_i32::<LittleEndian>()?;
-            let _incoming_reliable_ack = file.read_i32::<LittleEndian>()?;
-            let _incoming_reliable_seq = file.read_i32::<LittleEndian>()?;
-            let _outgoing_seq = file.read_i32::<LittleEndian>()?;
-            let _reliable_seq = file.read_i32::<LittleEndian>()?;
-            let _lastreliable_sez = file.read_i32::<LittleEndian>()?;
-            let msglength = file.read_i32::<LittleEndian>()? as usize;
-            let mut buffer = vec![0u8; msglength];
-
-            file.read_exact(&mut buffer)?;
-            let _chatmessage = buffer.iter()
-                .map(|&b| b as char)
-                .take_while(|&c| c != '\0')
-                .collect::<String>();
-            
-
-            let joined_cmds = commands_by_frame.remove(&frame)
-                .map(|cmds| cmds.iter().map(|c| format!("{}", c)).collect::<Vec<_>>())
-                .unwrap_or_default();
-            
-            // if !joined_cmds.is_empty() {
-            //     println!("{:#?}",joined_cmds);
-            // }
-            
-
-            frames.push(DemoFrame { 
-                frame: (frame), 
-                time: (time), 
-                vieworg: (vieworg),
-                viewangle: (viewangle), 
-                frametime: (frametime), 
-                onground: (onground), 
-                simvel: (simvel), 
-                simorg: (simorg), 
-                viewheight: (viewheight),
-                msec: (msec), 
-                gravity: (gravity), 
-                accelerate: (accelerate), 
-                airaccelerate: (airaccelerate), 
-                friction: (friction),
-                edgefriction: (edgefriction),
-                maxvelocity: (maxvelocitiy), 
-                command: (joined_cmds),
-                forwardmove: (forwardmove),
-                sidemove: (sidemove),
-                upmove: (upmove),
-                forward: (forward),
-                right: (right),
-                up: (up),
-            });
+    /// HLTV 관전 데모인지 여부. 이 경우 `frames` 의 simorg 는 카메라 위치다.
+    pub fn is_spectator(&self) -> bool {
+        self.frames.iter().any(|frame| frame.spectator)
+    }
+
+    /// `frames[index]` 에서 1인칭으로 관전 중인 플레이어의 슬롯
+    pub fn spectated_slot(&self, index: usize) -> Option<u8> {
+        let frame = self.frames.get(index)?;
+        let slot = u8::try_from(frame.viewentity.checked_sub(1)?).ok()?;
+
+        self.player(slot).map(|player| player.slot)
+    }
+
+    /// 움직임 분석에 넘길 프레임 묶음.
+    ///
+    /// 일반 데모는 `frames` 그대로 하나를 돌려준다. 관전 데모는 관전 대상이 바뀌지 않는 구간마다
+    /// 대상 플레이어의 엔티티 상태로 만든 프레임을 돌려준다. 대상이 바뀌는 곳에서 위치가 순간이동하므로
+    /// 구간을 이어붙이지 않는다.
+    pub fn movement_frames(&self) -> Vec<Cow<'_, [DemoFrame]>> {
+        if !self.is_spectator() {
+            return vec![Cow::Borrowed(&self.frames)];
         }
-        //파싱시작부
-        else if demo_type == 2 {
-            // println!("Demo Started")
+
+        let mut runs: Vec<Cow<'_, [DemoFrame]>> = Vec::new();
+        let mut start = 0;
+
+        while start < self.frames.len() {
+            let slot = self.spectated_slot(start);
+            let mut end = start + 1;
+            while end < self.frames.len() && self.spectated_slot(end) == slot {
+                end += 1;
+            }
+
+            if let Some(player) = slot.and_then(|slot| self.player(slot)) {
+                let frames = entity_demo_frames(&self.frames[start..end], &player.states[start..end]);
+                if !frames.is_empty() {
+                    runs.push(Cow::Owned(frames));
+                }
+            }
+
+            start = end;
         }
-        //커멘드
-        else if demo_type == 3 {
-            let cmd = read_fixed_string(&mut file, 64)?;
-            commands_by_frame.entry(frame).or_default().push(cmd);
+
+        runs
+    }
+}
+
+impl DemoFrame {
+    fn from_network_message(frame: i32, time: f32, message: &NetworkMessage, command: Vec<String>) -> DemoFrame {
+        let ref_params = &message.info.ref_params;
+        let user_cmd = &message.info.user_cmd;
+        let movevars = &message.info.movevars;
+
+        DemoFrame {
+            frame,
+            time,
+            vieworg: ref_params.vieworg.into(),
+            viewangle: ref_params.viewangles.into(),
+            frametime: ref_params.frametime,
+            onground: ref_params.onground != 0,
+            simvel: ref_params.simvel.into(),
+            simorg: ref_params.simorg.into(),
+            viewheight: ref_params.viewheight.into(),
+            msec: user_cmd.msec,
+            gravity: movevars.gravity,
+            accelerate: movevars.accelerate,
+            airaccelerate: movevars.airaccelerate,
+            friction: movevars.friction,
+            edgefriction: movevars.edgefriction,
+            maxvelocity: movevars.maxvelocity,
+            command,
+            forwardmove: user_cmd.forwardmove,
+            sidemove: user_cmd.sidemove,
+            upmove: user_cmd.upmove,
+            buttons: InputButtons::from_bits_retain(user_cmd.buttons),
+            estimated_buttons: InputButtons::empty(),
+            impulse: user_cmd.impulse as u8,
+            weaponselect: user_cmd.weaponselect as u8,
+            forward: ref_params.forward.into(),
+            right: ref_params.right.into(),
+            up: ref_params.up.into(),
+            spectator: ref_params.spectator != 0,
+            viewentity: ref_params.viewentity,
+            ref_params: ref_params.clone(),
+            user_cmd: user_cmd.clone(),
+            movevars: movevars.clone(),
+            current_weapon: None,
         }
-        //클라이언트 내부 지표 - 스킵
-        else if demo_type == 4{
-            file.seek(SeekFrom::Current(32))?;
+    }
+
+    /// 이동에 쓰이는 최대 속도. 들고 있는 무기의 속도와 sv_maxspeed 중 작은 값이다.
+    /// 무기를 알 수 없으면 sv_maxspeed 다.
+    pub fn maxspeed(&self) -> f32 {
+        self.current_weapon.map_or(self.movevars.maxspeed, |weapon| {
+            weapon.maxspeed().min(self.movevars.maxspeed)
+        })
+    }
+}
+
+/// 프레임은 읽지 않고 헤더와 디렉토리만 읽는다.
+pub fn parse_metadata(path: &str) -> io::Result<(DemoHeader, Vec<DirectoryEntry>)> {
+    Ok(parse_metadata_bytes(&std::fs::read(path)?)?)
+}
+
+pub fn parse_metadata_bytes(
+    bytes: &[u8],
+) -> Result<(DemoHeader, Vec<DirectoryEntry>), DemoParseError> {
+    let header = parse_header_checked(bytes)?;
+    let directory = parse_directory_checked(bytes, &header)?;
+
+    Ok((
+        DemoHeader::from(&header),
+        directory.entries.iter().map(DirectoryEntry::from).collect(),
+    ))
+}
+
+pub fn parse(path: &str) -> io::Result<ParsedDemo> {
+    Ok(parse_bytes(&std::fs::read(path)?)?)
+}
+
+/// 깨진 프레임을 건너뛰고 읽는다. 건너뛴 구간은 `ParsedDemo::recovery` 에 남는다.
+pub fn parse_lenient(path: &str) -> io::Result<ParsedDemo> {
+    Ok(parse_bytes_lenient(&std::fs::read(path)?)?)
+}
+
+/// 클라이언트가 죽어 디렉토리가 기록되지 않은 데모도 마지막 완전한 프레임까지 읽는다.
+/// 디렉토리를 다시 만들었는지는 `ParsedDemo::recovery` 로 알 수 있다.
+pub fn parse_recover(path: &str) -> io::Result<ParsedDemo> {
+    Ok(parse_bytes_recover(&std::fs::read(path)?)?)
+}
+
+/// 스트림 끝까지 읽어 파싱한다. 프레임을 하나씩 처리하려면 `DemoReader` 를 쓴다.
+pub fn parse_reader(mut reader: impl Read) -> io::Result<ParsedDemo> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+
+    Ok(parse_bytes(&bytes)?)
+}
+
+pub fn parse_bytes(bytes: &[u8]) -> Result<ParsedDemo, DemoParseError> {
+    parse_bytes_with(bytes, ErrorMode::Strict)
+}
+
+pub fn parse_bytes_lenient(bytes: &[u8]) -> Result<ParsedDemo, DemoParseError> {
+    parse_bytes_with(bytes, ErrorMode::Lenient)
+}
+
+pub fn parse_bytes_recover(bytes: &[u8]) -> Result<ParsedDemo, DemoParseError> {
+    parse_bytes_with(bytes, ErrorMode::Recover)
+}
+
+fn parse_bytes_with(bytes: &[u8], error_mode: ErrorMode) -> Result<ParsedDemo, DemoParseError> {
+    // HLDEMO 매직스트링
+    let header = parse_header_checked(bytes)?;
+    check_protocol(&header)?;
+    let game_mod = GameMod::from_game_dir(&header.game_directory());
+
+    // 다른 플레이어 추적에 엔티티 델타가 필요하므로 네트워크 메세지까지 해석한다
+    let (demo, recovery) = parse_demo_checked(bytes, MsgDataParseMode::Parse, error_mode)?;
+
+    let mut segments: Vec<DemoSegment> = Vec::new();
+    let mut frames: Vec<DemoFrame> = Vec::new();
+    let mut loading_frames: Vec<DemoFrame> = Vec::new();
+    let mut events: Vec<IndexedEvent> = Vec::new();
+    let mut trackers = Trackers::default();
+
+    for (index, entry) in demo.directory.entries.iter().enumerate() {
+        let target = if entry.type_ == DIRECTORY_ENTRY_LOADING {
+            &mut loading_frames
+        } else {
+            &mut frames
+        };
+        let start = target.len();
+        segment_frames(index, entry, &game_mod, target, &mut events, &mut trackers);
+
+        segments.push(DemoSegment {
+            entry_type: entry.type_,
+            title: entry.description(),
+            frames: start..target.len(),
+        });
+    }
+
+    // 처음 무기를 바꾸기 전까지는 그때 내려놓은 무기를 들고 있었다
+    if let Some(weapon) = trackers.weapons.held_before() {
+        for frame in frames
+            .iter_mut()
+            .take_while(|frame| frame.current_weapon.is_none())
+        {
+            frame.current_weapon = Some(weapon);
+        }
+    }
+
+    Ok(ParsedDemo {
+        header: DemoHeader::from(&demo.header),
+        directory: demo.directory.entries.iter().map(DirectoryEntry::from).collect(),
+        segments,
+        frames,
+        loading_frames,
+        events,
+        players: trackers.players.finish(),
+        hostname: server_hostname(&demo),
+        resources: resource_list(&demo),
+        recovery,
+    })
+}
+
+/// 데모 전체의 svc_resourcelist 리소스. 보통 LOADING 세그먼트에 한 번 온다.
+fn resource_list(demo: &types::Demo) -> Vec<Resource> {
+    engine_messages(demo)
+        .filter_map(|message| match message {
+            EngineMessage::SvcResourceList(list) => Some(&list.resources),
+            _ => None,
+        })
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+/// 처음 받은 svc_serverinfo 의 서버 이름. LOADING 세그먼트에 온다.
+fn server_hostname(demo: &types::Demo) -> String {
+    engine_messages(demo)
+        .find_map(|message| match message {
+            EngineMessage::SvcServerInfo(info) => Some(bytes_to_string(&info.hostname)),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// 데모 전체의 해석된 엔진 메세지 (파일 순서)
+fn engine_messages(demo: &types::Demo) -> impl Iterator<Item = &EngineMessage> {
+    demo.directory
+        .entries
+        .iter()
+        .flat_map(|entry| &entry.frames)
+        .filter_map(|frame| match &frame.frame_data {
+            FrameData::NetworkMessage(message) => match &message.1.messages {
+                MessageData::Parse(messages) => Some(messages),
+                _ => None,
+            },
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|message| match message {
+            NetMessage::EngineMessage(message) => Some(message.as_ref()),
+            _ => None,
+        })
+}
+
+/// 세그먼트를 넘어 이어지는 상태
+#[derive(Default)]
+struct Trackers {
+    entities: EntityTracker,
+    players: PlayerTracker,
+    weapons: WeaponTracker,
+}
+
+/// 세그먼트 하나의 네트워크 메세지 프레임을 DemoFrame 으로 변환해 `frames` 에 추가하고,
+/// 모든 프레임을 `events` 에 순서대로 추가한다. `frames` 는 LOADING 세그먼트면 `ParsedDemo::loading_frames`,
+/// 아니면 `ParsedDemo::frames` 다.
+/// 커멘드 프레임은 같은 프레임 번호의 DemoFrame 에도 붙인다.
+/// 엔티티, 플레이어, 무기 상태는 세그먼트를 넘어 이어지므로 밖에서 받는다.
+fn segment_frames(
+    segment: usize,
+    entry: &types::DirectoryEntry,
+    game_mod: &GameMod,
+    frames: &mut Vec<DemoFrame>,
+    events: &mut Vec<IndexedEvent>,
+    trackers: &mut Trackers,
+) {
+    let Trackers {
+        entities,
+        players,
+        weapons,
+    } = trackers;
+    let loading = entry.type_ == DIRECTORY_ENTRY_LOADING;
+    let mut commands_by_frame: HashMap<i32, Vec<String>> = HashMap::new();
+
+    for frame in &entry.frames {
+        let mut frame_events = frame_events(segment, frame, game_mod, &mut commands_by_frame);
+        weapons.update(&mut frame_events);
+
+        if let FrameData::NetworkMessage(message) = &frame.frame_data
+            && let Some(DemoEvent::NetworkMessage(_, demo_frame)) = frame_events.first()
+        {
+            if let MessageData::Parse(messages) = &message.1.messages {
+                entities.update(message.1.sequence_info.incoming_sequence, messages);
+            }
+            players.observe(&message.1);
+            // ParsedDemo::frames 와 같은 프레임만 기록한다
+            if !loading {
+                players.record(demo_frame, entities);
+            }
         }
-        //EOF 플래그
-        else if demo_type == 5 {
-            // println!("EOF.");
-            break;
+
+        events.extend(frame_events.into_iter().map(|event| {
+            event.map_frame(|demo_frame| {
+                frames.push(*demo_frame);
+
+                if loading {
+                    FrameIndex::Loading(frames.len() - 1)
+                } else {
+                    FrameIndex::Playback(frames.len() - 1)
+                }
+            })
+        }));
+    }
+}
+
+/// 프레임 하나를 이벤트로 변환한다.
+///
+/// 네트워크 메세지 프레임은 NetworkMessage 이벤트 뒤에 사용자 메세지 이벤트가 이어진다.
+/// 커멘드 프레임은 `commands_by_frame` 에 모아 두었다가 같은 프레임 번호의 DemoFrame 에도 붙인다.
+/// 프레임 번호는 세그먼트 안에서 줄어들지 않으므로 지나간 번호의 커멘드는 버린다.
+/// 사용자 메세지는 CS 계열 모드에서만 해석하고, 다른 모드는 `Other` 로 남긴다.
+pub(crate) fn frame_events(
+    segment: usize,
+    frame: &types::Frame,
+    game_mod: &GameMod,
+    commands_by_frame: &mut HashMap<i32, Vec<String>>,
+) -> Vec<DemoEvent> {
+    let header = DemoEventHeader {
+        segment,
+        time: frame.time,
+        frame: frame.frame,
+    };
+
+    let event = match &frame.frame_data {
+        //네트워크 메세지 - 실제 서버 통신내용
+        FrameData::NetworkMessage(message) => {
+            let joined_cmds = commands_by_frame.remove(&frame.frame).unwrap_or_default();
+            commands_by_frame.retain(|&command_frame, _| command_frame > frame.frame);
+
+            let demo_frame =
+                DemoFrame::from_network_message(frame.frame, frame.time, &message.1, joined_cmds);
+
+            let mut events = vec![DemoEvent::NetworkMessage(header, Box::new(demo_frame))];
+
+            if let MessageData::Parse(messages) = &message.1.messages {
+                events.extend(messages.iter().filter_map(|message| match message {
+                    NetMessage::UserMessage(message) if game_mod.is_counter_strike() => {
+                        Some(DemoEvent::UserMessage(header, CsUserMessage::parse(message)))
+                    }
+                    NetMessage::UserMessage(message) => Some(DemoEvent::UserMessage(
+                        header,
+                        CsUserMessage::Other(message.clone()),
+                    )),
+                    _ => None,
+                }));
+            }
+
+            return events;
         }
-        //이벤트 상호작용 데이터 - 스킵
-        else if demo_type == 6 {
-            file.seek(SeekFrom::Current(84))?;
+        //파싱시작부
+        FrameData::DemoStart => DemoEvent::DemoStart(header),
+        //커멘드
+        FrameData::ConsoleCommand(command) => {
+            let command = command.command();
+            commands_by_frame.entry(frame.frame).or_default().push(command.clone());
+
+            DemoEvent::ConsoleCommand(header, command)
         }
-        //무기 에니메이션 데이터 - 스킵
-        else if demo_type == 7 {
-            file.seek(SeekFrom::Current(8))?;
+        //클라이언트 내부 지표
+        FrameData::ClientData(client_data) => DemoEvent::ClientData(header, client_data.clone()),
+        //세그먼트 종료 플래그
+        FrameData::NextSection => DemoEvent::NextSection(header),
+        //이벤트 상호작용 데이터
+        FrameData::Event(event) => DemoEvent::Event(header, event.clone()),
+        //무기 에니메이션 데이터
+        FrameData::WeaponAnimation(animation) => {
+            DemoEvent::WeaponAnimation(header, animation.clone())
         }
         //사운드 데이터
-        else if demo_type == 8 {
-            let _channel = file.read_i32::<LittleEndian>()?;
-            let sample_len = file.read_i32::<LittleEndian>()? as usize;
-            let mut buffer = vec![0u8; sample_len];
-            file.read_exact(&mut buffer)?;
-            let _sample = String::from_utf8_lossy(&buffer).trim_end_matches('\0').to_string();
-            let _attenuation = file.read_f32::<LittleEndian>()?;
-            let _volume = file.read_f32::<LittleEndian>()?;
-            let _flags = file.read_i32::<LittleEndian>()?;
-            let _pitch = file.read_i32::<LittleEndian>()?;
-        }
-        else if demo_type == 9 {
-            let buffer_length = file.read_i32::<LittleEndian>()? as usize;
-            let mut buffer = vec![0u8; buffer_length];
-            file.read_exact(&mut buffer)?;
-        } else {
-            println!("Skipping unhandled segment type: {}", demo_type);
-            file.seek(SeekFrom::Current(4))?;
+        FrameData::Sound(sound) => DemoEvent::Sound(header, sound.clone()),
+        FrameData::DemoBuffer(buffer) => DemoEvent::DemoBuffer(header, buffer.buffer.clone()),
+    };
+
+    vec![event]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SkippedBytes;
+    use crate::parse::{DIRECTORY_ENTRY_SIZE, HEADER_SIZE};
+
+    /// 헤더의 마지막 필드가 디렉토리 오프셋이다
+    const DIRECTORY_OFFSET_FIELD: usize = HEADER_SIZE - 4;
+    /// 재생 세그먼트의 DemoStart(9 바이트) 다음 프레임. 21 바이트짜리 DemoBuffer 다.
+    const BUFFER_AFTER_START: usize = 9;
+    /// LOADING 세그먼트의 프레임 수. 재생 세그먼트 두 번째 프레임의 순번이 19 다.
+    const BUFFER_FRAME_INDEX: usize = 19;
+
+    fn read_test_demo() -> (Vec<u8>, usize) {
+        let bytes = std::fs::read("test/274_dcj_Desu.dem").unwrap();
+        let (_, directory) = parse_metadata_bytes(&bytes).unwrap();
+
+        (bytes, directory[1].offset as usize)
+    }
+
+    fn set_directory_offset(bytes: &mut [u8], offset: usize) {
+        bytes[DIRECTORY_OFFSET_FIELD..DIRECTORY_OFFSET_FIELD + 4]
+            .copy_from_slice(&(offset as i32).to_le_bytes());
+    }
+
+    #[test]
+    fn loading_segment_keeps_its_frames() {
+        let demo = parse("test/274_dcj_Desu.dem").unwrap();
+
+        let segments: Vec<_> = demo
+            .segments
+            .iter()
+            .map(|segment| (segment.title.as_str(), segment.frames.clone()))
+            .collect();
+        assert_eq!(segments, [("LOADING", 0..17), ("Playback", 0..2717)]);
+        assert_eq!(demo.segment_frames(&demo.segments[0]).len(), 17);
+
+        // 모든 네트워크 메세지 이벤트가 저장된 프레임을 가리킨다
+        for event in &demo.events {
+            if let DemoEvent::NetworkMessage(header, _) = event {
+                let frame = demo.event_frame(event).unwrap();
+                assert_eq!((frame.frame, frame.time), (header.frame, header.time));
+            }
         }
     }
 
-    Ok(frames)
+    #[test]
+    fn strict_parse_reports_truncated_frame() {
+        let (bytes, playback) = read_test_demo();
+        let frame = playback + BUFFER_AFTER_START;
+
+        // DemoBuffer 프레임 중간에서 자르고 디렉토리를 그 뒤에 붙인다
+        let directory_offset = parse_metadata_bytes(&bytes).unwrap().0.dir_offset as usize;
+        let cut = frame + 10;
+        let mut truncated = [&bytes[..cut], &bytes[directory_offset..]].concat();
+        set_directory_offset(&mut truncated, cut);
+
+        let error = parse_bytes(&truncated).unwrap_err();
+        assert_eq!(
+            error,
+            DemoParseError::TruncatedFrame {
+                offset: frame,
+                frame_index: BUFFER_FRAME_INDEX,
+            }
+        );
+        assert_eq!(error.offset(), frame);
+        assert_eq!(error.frame_index(), Some(BUFFER_FRAME_INDEX));
+    }
+
+    #[test]
+    fn lenient_parse_skips_corrupted_frame() {
+        let (mut bytes, playback) = read_test_demo();
+        let frame = playback + BUFFER_AFTER_START;
+        bytes[frame] = 0xff;
+
+        let error = DemoParseError::UnknownFrameType {
+            frame_type: 0xff,
+            offset: frame,
+            frame_index: BUFFER_FRAME_INDEX,
+        };
+        assert_eq!(parse_bytes(&bytes).unwrap_err(), error);
+
+        let demo = parse_bytes_lenient(&bytes).unwrap();
+        assert_eq!(
+            demo.recovery,
+            Recovery {
+                skipped: vec![SkippedBytes {
+                    offset: frame,
+                    length: 21,
+                    error,
+                }],
+                ..Recovery::default()
+            }
+        );
+        assert_eq!(demo.frames.len(), 2717);
+    }
+
+    #[test]
+    fn recover_parse_synthesizes_directory() {
+        let (mut bytes, _) = read_test_demo();
+        let original = parse_bytes(&bytes).unwrap();
+        set_directory_offset(&mut bytes, 0);
+
+        assert_eq!(
+            parse_bytes(&bytes).unwrap_err(),
+            DemoParseError::BadDirectory { offset: 0 }
+        );
+
+        let demo = parse_bytes_recover(&bytes).unwrap();
+        assert!(demo.recovery.directory_synthesized);
+        assert!(demo.recovery.skipped.is_empty());
+        // 원래 디렉토리(엔트리 수 4 바이트와 엔트리 2개)만 프레임이 아니다
+        assert_eq!(demo.recovery.truncated_bytes, 4 + 2 * DIRECTORY_ENTRY_SIZE);
+
+        let segments: Vec<_> = demo
+            .segments
+            .iter()
+            .map(|segment| (segment.title.as_str(), segment.frames.clone()))
+            .collect();
+        assert_eq!(segments, [("LOADING", 0..17), ("Playback", 0..2717)]);
+        assert_eq!(
+            demo.directory[1].frame_count,
+            original.directory[1].frame_count
+        );
+        assert_eq!(demo.directory[1].time, original.directory[1].time);
+
+        for (frame, expected) in demo.frames.iter().zip(&original.frames) {
+            assert_eq!((frame.frame, frame.time), (expected.frame, expected.time));
+        }
+    }
 }
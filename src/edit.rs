@@ -0,0 +1,774 @@
+//! 데모 편집 모듈
+//!
+//! `MsgDataParseMode::Raw` 로 읽은 Demo 를 고쳐 `DemoWriter` 로 다시 쓸 수 있는 Demo 를 만든다.
+
+use std::ops::Range;
+
+use crate::analyze::JumpSegment;
+use crate::demo::{DIRECTORY_ENTRY_LOADING, DemoFrame, ParsedDemo};
+use crate::entity::EntityTracker;
+use crate::error::{CutError, MergeError};
+use crate::parse_netmsg::{
+    SVC_DELTADESCRIPTION, SVC_NEWUSERMSG, SVC_RESOURCELIST, SVC_SPAWNBASELINE, SVC_UPDATEUSERINFO,
+    parse_netmsg_spans,
+};
+use crate::types::{
+    Aux, AuxRefCell, Demo, Directory, DirectoryEntry, EngineMessage, Frame, FrameData, MessageData,
+    NetMessage, NetworkMessage, bytes_to_string,
+};
+
+/// `start`..=`end` 초 구간의 프레임만 남긴 데모를 만든다.
+///
+/// 게임에서 불러올 수 있도록 LOADING 세그먼트는 그대로 두고,
+/// 재생 세그먼트는 시간과 프레임 번호가 0 부터 시작하도록 당긴 뒤 디렉토리를 다시 계산한다.
+/// 남긴 프레임의 엔티티 델타나 clientdata 가 잘라낸 패킷을 기준으로 하면 그 메세지는
+/// 같은 상태를 담은 전체 갱신(svc_packetentities, 기준 패킷 없는 svc_clientdata)으로 바꾼다.
+/// svc_time 은 서버 시각이라 원본 데모에서도 프레임 시간과 다르므로 그대로 둔다.
+pub fn cut_demo(demo: &Demo, start: f32, end: f32) -> Result<Demo, CutError> {
+    if start.is_nan() || end.is_nan() || start > end {
+        return Err(CutError::InvalidRange { start, end });
+    }
+
+    let mut rebase = DeltaRebase::new();
+
+    let entries = demo
+        .directory
+        .entries
+        .iter()
+        .map(|entry| {
+            if entry.type_ == DIRECTORY_ENTRY_LOADING {
+                let frames = entry
+                    .frames
+                    .iter()
+                    .map(|frame| {
+                        let frame_data = rebase.frame(frame, true)?.unwrap();
+                        Ok(Frame {
+                            frame_data,
+                            ..frame.clone()
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                Ok(DirectoryEntry {
+                    frames,
+                    trailing: vec![],
+                    ..entry.clone()
+                })
+            } else {
+                cut_entry(entry, start, end, &mut rebase)
+            }
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Demo {
+        header: demo.header.clone(),
+        directory: Directory { entries },
+    })
+}
+
+/// 점프 구간 앞뒤로 `margin` 초를 더 남긴다.
+pub fn cut_jump_segment(demo: &Demo, segment: &JumpSegment, margin: f32) -> Result<Demo, CutError> {
+    let start = segment.frames.first().map_or(0.0, |frame| frame.time);
+    let end = segment.frames.last().map_or(0.0, |frame| frame.time);
+
+    cut_demo(demo, start - margin, end + margin)
+}
+
+fn cut_entry(
+    entry: &DirectoryEntry,
+    start: f32,
+    end: f32,
+    rebase: &mut DeltaRebase,
+) -> Result<DirectoryEntry, CutError> {
+    // 첫 네트워크 메세지 전의 DemoStart, DemoBuffer 등은 재생 초기화에 필요하므로 남긴다
+    let leading = entry
+        .frames
+        .iter()
+        .take_while(|frame| !matches!(frame.frame_data, FrameData::NetworkMessage(_)))
+        .count();
+
+    let (time_offset, frame_offset) = entry.frames[leading..]
+        .iter()
+        .filter(|frame| !matches!(frame.frame_data, FrameData::NextSection))
+        .find(|frame| (start..=end).contains(&frame.time))
+        .map_or((0.0, 0), |frame| (frame.time, frame.frame));
+
+    let mut frames: Vec<Frame> = entry.frames[..leading].to_vec();
+    for frame in &entry.frames[leading..] {
+        if matches!(frame.frame_data, FrameData::NextSection) {
+            continue;
+        }
+
+        // 잘라낼 프레임도 엔티티 상태를 따라가야 하므로 모두 거친다
+        if let Some(frame_data) = rebase.frame(frame, (start..=end).contains(&frame.time))? {
+            frames.push(Frame {
+                time: frame.time - time_offset,
+                frame: frame.frame - frame_offset,
+                frame_data,
+            });
+        }
+    }
+
+    let (time, frame) = frames
+        .last()
+        .map_or((0.0, 0), |last| (last.time, last.frame));
+    frames.push(Frame {
+        time,
+        frame,
+        frame_data: FrameData::NextSection,
+    });
+
+    Ok(DirectoryEntry {
+        type_: entry.type_,
+        description: entry.description.clone(),
+        flags: entry.flags,
+        cd_track: entry.cd_track,
+        track_time: time,
+        frame_count: frame + 1,
+        // 오프셋과 길이는 DemoWriter 가 쓴 위치로 채운다
+        offset: 0,
+        file_length: 0,
+        frames,
+        trailing: vec![],
+    })
+}
+
+/// 데모의 모든 네트워크 메세지를 따라가며 잘라낸 패킷을 기준으로 한 델타를 찾는다.
+struct DeltaRebase {
+    /// 델타 디코더와 사용자 메세지 크기를 알아야 메세지 경계를 찾을 수 있다
+    aux: AuxRefCell,
+    entities: EntityTracker,
+    /// `incoming_sequence & 0xFF` 별로 그 번호의 마지막 패킷을 잘라냈는지
+    dropped: [bool; 256],
+}
+
+impl DeltaRebase {
+    fn new() -> Self {
+        DeltaRebase {
+            aux: Aux::new_ref_cell(),
+            entities: EntityTracker::new(),
+            dropped: [false; 256],
+        }
+    }
+
+    /// 프레임 하나를 상태에 반영한다. 남기는 프레임이면 다시 쓸 프레임 데이터를 돌려준다.
+    fn frame(&mut self, frame: &Frame, keep: bool) -> Result<Option<FrameData>, CutError> {
+        let FrameData::NetworkMessage(message) = &frame.frame_data else {
+            return Ok(keep.then(|| frame.frame_data.clone()));
+        };
+
+        let (message_type, message) = message.as_ref();
+        let MessageData::Raw(payload) = &message.messages else {
+            return Err(CutError::NotRaw { frame: frame.frame });
+        };
+
+        self.aux.borrow_mut().is_hltv = message.info.ref_params.spectator != 0;
+        let (spans, messages): (Vec<Range<usize>>, Vec<NetMessage>) =
+            parse_netmsg_spans(payload, &self.aux).into_iter().unzip();
+
+        let sequence = message.sequence_info.incoming_sequence;
+        self.entities.update(sequence, &messages);
+
+        let frame_data = keep.then(|| match self.rebase(payload, &spans, &messages) {
+            Some(payload) => FrameData::NetworkMessage(Box::new((
+                *message_type,
+                NetworkMessage {
+                    message_length: payload.len() as i32,
+                    messages: MessageData::Raw(payload),
+                    ..message.clone()
+                },
+            ))),
+            None => frame.frame_data.clone(),
+        });
+        self.dropped[sequence as u8 as usize] = !keep;
+
+        Ok(frame_data)
+    }
+
+    /// 잘라낸 패킷을 기준으로 한 메세지를 방금 반영한 상태의 전체 갱신으로 바꾼 페이로드.
+    /// 바꿀 메세지가 없으면 None.
+    fn rebase(
+        &self,
+        payload: &[u8],
+        spans: &[Range<usize>],
+        messages: &[NetMessage],
+    ) -> Option<Vec<u8>> {
+        let aux = self.aux.borrow();
+        let mut rebased = Vec::with_capacity(payload.len());
+        let mut changed = false;
+
+        for (span, message) in spans.iter().zip(messages) {
+            let replacement = match message {
+                NetMessage::EngineMessage(engine_message) => match engine_message.as_ref() {
+                    EngineMessage::SvcDeltaPacketEntities(packet)
+                        if self.dropped[packet.delta_sequence as usize] =>
+                    {
+                        self.entities.full_packet_entities(&aux)
+                    }
+                    EngineMessage::SvcClientData(client_data)
+                        if client_data
+                            .delta_update_mask
+                            .is_some_and(|sequence| self.dropped[sequence as usize]) =>
+                    {
+                        self.entities.full_client_data(&aux)
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            match replacement {
+                Some(bytes) => {
+                    rebased.extend_from_slice(&bytes);
+                    changed = true;
+                }
+                None => rebased.extend_from_slice(&payload[span.clone()]),
+            }
+        }
+
+        changed.then_some(rebased)
+    }
+}
+
+/// 합칠 데모끼리 같아야 하는 LOADING 세그먼트 메세지
+const SIGNON_MESSAGES: [(u8, &str); 4] = [
+    (SVC_DELTADESCRIPTION, "svc_deltadescription"),
+    (SVC_NEWUSERMSG, "svc_newusermsg"),
+    (SVC_RESOURCELIST, "svc_resourcelist"),
+    (SVC_SPAWNBASELINE, "svc_spawnbaseline"),
+];
+
+/// 같은 서버, 같은 맵에서 나눠 녹화한 데모들을 재생 세그먼트 하나로 이어 붙인다.
+///
+/// LOADING 세그먼트는 첫 데모의 것을 쓰므로 뒤 데모의 델타 디코더, 사용자 메세지 번호, 리소스,
+/// 베이스라인은 첫 데모와 같아야 한다. 뒤 데모의 LOADING 세그먼트에 있던 플레이어 정보(svc_updateuserinfo)는
+/// 그 데모의 첫 네트워크 메세지 앞에 넣고, 그 데모 밖의 패킷을 기준으로 한 델타는 전체 갱신으로 바꾼다.
+/// 뒤 데모의 시간과 프레임 번호는 앞 데모의 마지막 프레임에서 한 프레임 뒤에 이어지도록 민다.
+pub fn merge_demos(demos: &[Demo]) -> Result<Demo, MergeError> {
+    check_same_map(
+        demos
+            .iter()
+            .map(|demo| (demo.header.map_name(), demo.header.map_checksum)),
+    )?;
+
+    let loadings = demos
+        .iter()
+        .enumerate()
+        .map(|(index, demo)| Loading::from_demo(demo, index))
+        .collect::<Result<Vec<_>, _>>()?;
+    check_same_loading(&loadings)?;
+
+    let first = &demos[0];
+    let mut entries: Vec<DirectoryEntry> = first
+        .directory
+        .entries
+        .iter()
+        .filter(|entry| entry.type_ == DIRECTORY_ENTRY_LOADING)
+        .take(1)
+        .map(|entry| DirectoryEntry {
+            trailing: vec![],
+            ..entry.clone()
+        })
+        .collect();
+
+    let mut frames: Vec<Frame> = vec![];
+    let mut playback: Option<&DirectoryEntry> = None;
+
+    for (index, (demo, loading)) in demos.iter().zip(&loadings).enumerate() {
+        let (time_offset, frame_offset) = frames.last().map_or((0.0, 0), |last| {
+            (last.time + last_frametime(&frames), last.frame + 1)
+        });
+
+        // 뒤 데모에서는 앞 데모의 패킷이 기준 패킷 번호를 차지하므로 이 데모에서 남긴 패킷만 믿는다
+        let mut rebase = (index > 0).then(|| DeltaRebase {
+            dropped: [true; 256],
+            ..DeltaRebase::new()
+        });
+        let mut user_info = (index > 0).then_some(loading.user_info.as_slice());
+
+        for entry in &demo.directory.entries {
+            if entry.type_ == DIRECTORY_ENTRY_LOADING {
+                // 델타 디코더와 엔티티 상태만 반영한다
+                if let Some(rebase) = &mut rebase {
+                    for frame in &entry.frames {
+                        rebase
+                            .frame(frame, false)
+                            .map_err(|_| MergeError::NotRaw { index })?;
+                    }
+                }
+                continue;
+            }
+
+            playback.get_or_insert(entry);
+
+            // 세그먼트 경계 표시는 합친 세그먼트의 처음과 끝에만 둔다
+            let is_first = frames.is_empty();
+
+            for frame in &entry.frames {
+                match frame.frame_data {
+                    FrameData::DemoStart if !is_first => continue,
+                    FrameData::NextSection => continue,
+                    _ => {}
+                }
+
+                let mut frame_data = match &mut rebase {
+                    Some(rebase) => rebase
+                        .frame(frame, true)
+                        .map_err(|_| MergeError::NotRaw { index })?
+                        .unwrap(),
+                    None => frame.frame_data.clone(),
+                };
+
+                if let FrameData::NetworkMessage(message) = &mut frame_data
+                    && let MessageData::Raw(payload) = &mut message.1.messages
+                    && let Some(user_info) = user_info.take()
+                {
+                    payload.splice(0..0, user_info.iter().copied());
+                    message.1.message_length = payload.len() as i32;
+                }
+
+                frames.push(Frame {
+                    time: frame.time + time_offset,
+                    frame: frame.frame + frame_offset,
+                    frame_data,
+                });
+            }
+        }
+    }
+
+    let (time, frame) = frames
+        .last()
+        .map_or((0.0, 0), |last| (last.time, last.frame));
+    frames.push(Frame {
+        time,
+        frame,
+        frame_data: FrameData::NextSection,
+    });
+
+    if let Some(playback) = playback {
+        entries.push(DirectoryEntry {
+            type_: playback.type_,
+            description: playback.description.clone(),
+            flags: playback.flags,
+            cd_track: playback.cd_track,
+            track_time: time,
+            frame_count: frame + 1,
+            offset: 0,
+            file_length: 0,
+            frames,
+            trailing: vec![],
+        });
+    }
+
+    Ok(Demo {
+        header: first.header.clone(),
+        directory: Directory { entries },
+    })
+}
+
+/// 마지막 네트워크 메세지 프레임의 frametime
+fn last_frametime(frames: &[Frame]) -> f32 {
+    frames
+        .iter()
+        .rev()
+        .find_map(|frame| match &frame.frame_data {
+            FrameData::NetworkMessage(message) => Some(message.1.info.ref_params.frametime),
+            _ => None,
+        })
+        .unwrap_or(0.0)
+}
+
+/// 합치기 위해 LOADING 세그먼트에서 모은 것
+#[derive(Debug, Default)]
+struct Loading {
+    /// svc_serverinfo 의 서버 이름
+    hostname: String,
+    /// `SIGNON_MESSAGES` 순서대로 메세지 원본 바이트를 이어 붙인 것
+    signon: [Vec<u8>; SIGNON_MESSAGES.len()],
+    /// svc_updateuserinfo 원본 바이트
+    user_info: Vec<u8>,
+}
+
+impl Loading {
+    fn from_demo(demo: &Demo, index: usize) -> Result<Loading, MergeError> {
+        let aux = Aux::new_ref_cell();
+        let mut loading = Loading::default();
+
+        for frame in demo
+            .directory
+            .entries
+            .iter()
+            .filter(|entry| entry.type_ == DIRECTORY_ENTRY_LOADING)
+            .flat_map(|entry| &entry.frames)
+        {
+            let FrameData::NetworkMessage(message) = &frame.frame_data else {
+                continue;
+            };
+            let MessageData::Raw(payload) = &message.1.messages else {
+                return Err(MergeError::NotRaw { index });
+            };
+
+            aux.borrow_mut().is_hltv = message.1.info.ref_params.spectator != 0;
+
+            for (span, parsed) in parse_netmsg_spans(payload, &aux) {
+                let bytes = &payload[span];
+
+                if let NetMessage::EngineMessage(engine_message) = &parsed
+                    && let EngineMessage::SvcServerInfo(info) = engine_message.as_ref()
+                {
+                    loading.hostname = bytes_to_string(&info.hostname);
+                }
+
+                if bytes[0] == SVC_UPDATEUSERINFO {
+                    loading.user_info.extend_from_slice(bytes);
+                } else if let Some(position) =
+                    SIGNON_MESSAGES.iter().position(|&(id, _)| id == bytes[0])
+                {
+                    loading.signon[position].extend_from_slice(bytes);
+                }
+            }
+        }
+
+        Ok(loading)
+    }
+}
+
+/// 모든 데모의 서버 이름과 LOADING 세그먼트 메세지가 첫 데모와 같은지 확인한다.
+fn check_same_loading(loadings: &[Loading]) -> Result<(), MergeError> {
+    let Some((expected, rest)) = loadings.split_first() else {
+        return Err(MergeError::Empty);
+    };
+
+    for (index, loading) in rest.iter().enumerate() {
+        let index = index + 1;
+
+        if loading.hostname != expected.hostname {
+            return Err(MergeError::HostnameMismatch {
+                index,
+                expected: expected.hostname.clone(),
+                found: loading.hostname.clone(),
+            });
+        }
+
+        if let Some(position) = (0..SIGNON_MESSAGES.len())
+            .find(|&position| loading.signon[position] != expected.signon[position])
+        {
+            return Err(MergeError::LoadingMismatch {
+                index,
+                message: SIGNON_MESSAGES[position].1,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// `merge_demos` 와 같은 방식으로 분석용 프레임만 이어 붙인다.
+/// `ParsedDemo` 에는 LOADING 세그먼트 메세지 원본이 없으므로 맵 이름, CRC, 서버 이름, 리소스 목록만 확인한다.
+pub fn merge_frames(demos: &[ParsedDemo]) -> Result<Vec<DemoFrame>, MergeError> {
+    check_same_map(
+        demos
+            .iter()
+            .map(|demo| (demo.header.map_name.clone(), demo.header.crc)),
+    )?;
+    check_same_server(demos)?;
+
+    let mut frames: Vec<DemoFrame> = vec![];
+
+    for demo in demos {
+        let (time_offset, frame_offset) = frames.last().map_or((0.0, 0), |last| {
+            (last.time + last.frametime, last.frame + 1)
+        });
+
+        frames.extend(demo.frames.iter().map(|frame| DemoFrame {
+            time: frame.time + time_offset,
+            frame: frame.frame + frame_offset,
+            ..frame.clone()
+        }));
+    }
+
+    Ok(frames)
+}
+
+/// 모든 데모의 서버 이름과 리소스 목록이 첫 데모와 같은지 확인한다.
+fn check_same_server(demos: &[ParsedDemo]) -> Result<(), MergeError> {
+    let Some((expected, rest)) = demos.split_first() else {
+        return Err(MergeError::Empty);
+    };
+    let resources = |demo: &ParsedDemo| {
+        demo.resources
+            .iter()
+            .map(|resource| (resource.type_, resource.index, resource.name.clone()))
+            .collect::<Vec<_>>()
+    };
+
+    for (index, demo) in rest.iter().enumerate() {
+        let index = index + 1;
+
+        if demo.hostname != expected.hostname {
+            return Err(MergeError::HostnameMismatch {
+                index,
+                expected: expected.hostname.clone(),
+                found: demo.hostname.clone(),
+            });
+        }
+
+        if resources(demo) != resources(expected) {
+            return Err(MergeError::LoadingMismatch {
+                index,
+                message: "svc_resourcelist",
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// 모든 데모의 맵 이름과 CRC 가 첫 데모와 같은지 확인한다.
+fn check_same_map(maps: impl Iterator<Item = (String, u32)>) -> Result<(), MergeError> {
+    let mut expected: Option<(String, u32)> = None;
+
+    for (index, (map_name, crc)) in maps.enumerate() {
+        let Some((expected_name, expected_crc)) = &expected else {
+            expected = Some((map_name, crc));
+            continue;
+        };
+
+        if !map_name.eq_ignore_ascii_case(expected_name) {
+            return Err(MergeError::MapMismatch {
+                index,
+                expected: expected_name.clone(),
+                found: map_name,
+            });
+        }
+
+        if crc != *expected_crc {
+            return Err(MergeError::CrcMismatch {
+                index,
+                expected: *expected_crc,
+                found: crc,
+            });
+        }
+    }
+
+    match expected {
+        Some(_) => Ok(()),
+        None => Err(MergeError::Empty),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::demo::parse_bytes;
+    use crate::entity::PacketFrame;
+    use crate::parse::{MsgDataParseMode, parse_demo};
+    use crate::types::{Delta, DeltaValue};
+    use crate::write::DemoWriter;
+
+    fn read_demo(mode: MsgDataParseMode) -> Demo {
+        let bytes = std::fs::read("test/274_dcj_Desu.dem").unwrap();
+        parse_demo(&bytes, mode).unwrap().1
+    }
+
+    /// 쓴 뒤 다시 해석한 데모
+    fn reparse(demo: &Demo) -> Demo {
+        let bytes = DemoWriter::write_demo(Cursor::new(vec![]), demo)
+            .unwrap()
+            .into_inner();
+        parse_demo(&bytes, MsgDataParseMode::Parse).unwrap().1
+    }
+
+    /// 재생 세그먼트의 네트워크 메세지 프레임마다 시각과 엔티티 상태.
+    /// 델타 패킷이 데모 안에 없는 패킷을 기준으로 하면 실패한다.
+    fn playback_states(demo: &Demo) -> Vec<(f32, PacketFrame)> {
+        let mut tracker = EntityTracker::new();
+        let mut received = HashSet::new();
+        let mut states = vec![];
+
+        for entry in &demo.directory.entries {
+            for frame in &entry.frames {
+                let FrameData::NetworkMessage(message) = &frame.frame_data else {
+                    continue;
+                };
+                let MessageData::Parse(messages) = &message.1.messages else {
+                    panic!("not parsed");
+                };
+
+                for message in messages {
+                    if let NetMessage::EngineMessage(engine_message) = message
+                        && let EngineMessage::SvcDeltaPacketEntities(packet) =
+                            engine_message.as_ref()
+                    {
+                        assert!(
+                            received.contains(&packet.delta_sequence),
+                            "frame {} deltas from missing packet {}",
+                            frame.frame,
+                            packet.delta_sequence
+                        );
+                    }
+                }
+
+                let sequence = message.1.sequence_info.incoming_sequence;
+                tracker.update(sequence, messages);
+                received.insert(sequence as u8);
+
+                if entry.type_ != DIRECTORY_ENTRY_LOADING {
+                    states.push((frame.time, tracker.current().clone()));
+                }
+            }
+        }
+
+        states
+    }
+
+    /// 한쪽에만 있는 필드는 0 으로 보고 비교한다.
+    fn same_fields(a: &Delta, b: &Delta) -> bool {
+        let value = |delta: &Delta, name: &String| match delta.get(name) {
+            Some(DeltaValue::Number { raw, .. }) => (*raw, vec![]),
+            Some(DeltaValue::String(string)) => (0, string.clone()),
+            None => (0, vec![]),
+        };
+
+        a.keys()
+            .chain(b.keys())
+            .all(|name| value(a, name) == value(b, name))
+    }
+
+    /// 엔티티, clientdata, weapon_data 가 프레임마다 같은지 확인한다.
+    fn assert_same_states(states: &[(f32, PacketFrame)], expected: &[(f32, PacketFrame)]) {
+        assert!(!expected.is_empty());
+        assert_eq!(states.len(), expected.len());
+
+        for ((_, state), (time, expected)) in states.iter().zip(expected) {
+            assert_eq!(
+                state.entities.keys().collect::<Vec<_>>(),
+                expected.entities.keys().collect::<Vec<_>>(),
+                "entities at {}",
+                time
+            );
+            for (index, entity) in &state.entities {
+                assert!(
+                    same_fields(&entity.fields, &expected.entities[index].fields),
+                    "entity {} at {}",
+                    index,
+                    time
+                );
+            }
+
+            assert!(same_fields(
+                &state.client_data.fields,
+                &expected.client_data.fields
+            ));
+            assert_eq!(state.weapon_data.len(), expected.weapon_data.len());
+            for (index, weapon) in &state.weapon_data {
+                assert!(same_fields(
+                    &weapon.fields,
+                    &expected.weapon_data[index].fields
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn cut_rewrites_deltas_from_dropped_packets() {
+        let (start, end) = (5.0, 10.0);
+        let cut = cut_demo(&read_demo(MsgDataParseMode::Raw), start, end).unwrap();
+
+        let expected: Vec<(f32, PacketFrame)> =
+            playback_states(&read_demo(MsgDataParseMode::Parse))
+                .into_iter()
+                .filter(|(time, _)| (start..=end).contains(time))
+                .collect();
+
+        assert_same_states(&playback_states(&reparse(&cut)), &expected);
+    }
+
+    #[test]
+    fn cut_rejects_reversed_range() {
+        let demo = read_demo(MsgDataParseMode::Raw);
+
+        assert_eq!(
+            cut_demo(&demo, 5.0, 1.0).unwrap_err(),
+            CutError::InvalidRange {
+                start: 5.0,
+                end: 1.0
+            }
+        );
+    }
+    #[test]
+    fn merge_continues_after_one_frame() {
+        let demo = read_demo(MsgDataParseMode::Raw);
+        let merged = merge_demos(&[demo.clone(), demo.clone()]).unwrap();
+
+        let playback = |demo: &Demo| -> Vec<Frame> {
+            demo.directory
+                .entries
+                .iter()
+                .filter(|entry| entry.type_ != DIRECTORY_ENTRY_LOADING)
+                .flat_map(|entry| entry.frames.clone())
+                .filter(|frame| !matches!(frame.frame_data, FrameData::NextSection))
+                .collect()
+        };
+        let original = playback(&demo);
+        let frames = playback(&merged);
+        let (last, next) = (&original[original.len() - 1], &frames[original.len()]);
+
+        assert_eq!(
+            next.time,
+            last.time + last_frametime(&original) + original[0].time
+        );
+        assert_eq!(next.frame, last.frame + 1 + original[0].frame);
+
+        // 뒤 데모도 원본과 같은 엔티티 상태로 재생된다
+        let expected = playback_states(&read_demo(MsgDataParseMode::Parse));
+        let states = playback_states(&reparse(&merged));
+        assert_same_states(&states[expected.len()..], &expected);
+    }
+
+    #[test]
+    fn merge_rejects_other_server() {
+        let demo = read_demo(MsgDataParseMode::Raw);
+        let hostname = Loading::from_demo(&demo, 0).unwrap().hostname;
+
+        // svc_serverinfo 의 서버 이름 첫 글자만 바꾼다
+        let mut other = demo.clone();
+        let payload = other
+            .directory
+            .entries
+            .iter_mut()
+            .flat_map(|entry| &mut entry.frames)
+            .find_map(|frame| match &mut frame.frame_data {
+                FrameData::NetworkMessage(message) => match &mut message.1.messages {
+                    MessageData::Raw(payload) => Some(payload),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .unwrap();
+        let position = payload
+            .windows(hostname.len())
+            .position(|window| window == hostname.as_bytes())
+            .unwrap();
+        payload[position] ^= 0x20;
+
+        let parsed = |demo: &Demo| {
+            let bytes = DemoWriter::write_demo(Cursor::new(vec![]), demo)
+                .unwrap()
+                .into_inner();
+            parse_bytes(&bytes).unwrap()
+        };
+        assert!(matches!(
+            merge_frames(&[parsed(&demo), parsed(&other)]),
+            Err(MergeError::HostnameMismatch { index: 1, .. })
+        ));
+
+        assert!(matches!(
+            merge_demos(&[demo, other]),
+            Err(MergeError::HostnameMismatch { index: 1, .. })
+        ));
+    }
+}
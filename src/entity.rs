@@ -0,0 +1,309 @@
+//! 엔티티 상태 추적 모듈
+//!
+//! svc_packetentities / svc_deltapacketentities / svc_clientdata 는 이전 패킷이나
+//! 베이스라인과의 차이만 보내므로, 패킷마다 전체 상태를 누적해 두어야 한다.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    bitstream::BitWriter,
+    delta::{apply_delta, diff_delta, write_delta},
+    parse_netmsg::{ENTITY_NORMAL, SVC_CLIENTDATA, SVC_PACKETENTITIES, entity_decoder_name},
+    types::*,
+};
+
+/// 엔티티(또는 clientdata_t, weapon_data_t) 하나의 전체 상태.
+/// 한 번도 전송되지 않은 필드는 0 으로 본다.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EntityState {
+    pub fields: Delta,
+    /// custom_entity_state_t 로 인코딩되는 엔티티 (빔 등)
+    pub custom: bool,
+}
+
+impl EntityState {
+    pub fn number(&self, name: &str) -> f32 {
+        self.fields
+            .get(name)
+            .and_then(DeltaValue::as_f32)
+            .unwrap_or(0.0)
+    }
+
+    pub fn integer(&self, name: &str) -> i32 {
+        self.fields
+            .get(name)
+            .and_then(DeltaValue::as_i32)
+            .unwrap_or(0)
+    }
+
+    /// `name[0]`, `name[1]`, `name[2]` 필드를 묶어 읽는다.
+    pub fn vector(&self, name: &str) -> [f32; 3] {
+        [0, 1, 2].map(|axis| self.number(&format!("{}[{}]", name, axis)))
+    }
+
+    pub fn origin(&self) -> [f32; 3] {
+        self.vector("origin")
+    }
+
+    pub fn angles(&self) -> [f32; 3] {
+        self.vector("angles")
+    }
+}
+
+/// 패킷 하나를 받은 직후의 전체 상태 (클라이언트의 frame_t)
+#[derive(Debug, Clone, Default)]
+pub struct PacketFrame {
+    pub entities: BTreeMap<u16, EntityState>,
+    pub client_data: EntityState,
+    pub weapon_data: BTreeMap<u8, EntityState>,
+}
+
+/// 네트워크 메세지 프레임을 순서대로 받아 모든 엔티티의 상태를 유지한다.
+///
+/// 델타 패킷은 `incoming_sequence & 0xFF` 로 기준 패킷을 가리키므로 최근 256 개 패킷을 보관한다.
+/// 기준 패킷이 없으면(잘린 데모 등) 마지막 상태를 기준으로 삼는다.
+#[derive(Debug, Clone, Default)]
+pub struct EntityTracker {
+    baselines: HashMap<u16, EntityState>,
+    instanced_baselines: Vec<EntityState>,
+    history: HashMap<u8, PacketFrame>,
+    current: PacketFrame,
+}
+
+impl EntityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 마지막으로 적용한 패킷 기준의 상태
+    pub fn current(&self) -> &PacketFrame {
+        &self.current
+    }
+
+    pub fn entity(&self, index: u16) -> Option<&EntityState> {
+        self.current.entities.get(&index)
+    }
+
+    /// 네트워크 메세지 프레임 하나를 적용한다.
+    /// `incoming_sequence` 는 같은 프레임의 `SequenceInfo::incoming_sequence` 다.
+    pub fn update(&mut self, incoming_sequence: i32, messages: &[NetMessage]) {
+        for message in messages {
+            let NetMessage::EngineMessage(message) = message else {
+                continue;
+            };
+
+            match message.as_ref() {
+                // 새 맵 접속: 이전 맵의 엔티티 번호는 의미가 없다
+                EngineMessage::SvcServerInfo(_) => *self = Self::default(),
+                EngineMessage::SvcSpawnBaseline(baseline) => self.spawn_baseline(baseline),
+                EngineMessage::SvcPacketEntities(packet) => self.packet_entities(packet),
+                EngineMessage::SvcDeltaPacketEntities(packet) => self.delta_packet_entities(packet),
+                EngineMessage::SvcClientData(client_data) => self.client_data(client_data),
+                _ => {}
+            }
+        }
+
+        self.history
+            .insert(incoming_sequence as u8, self.current.clone());
+    }
+
+    /// 현재 엔티티를 모두 담은 svc_packetentities (메세지 번호 포함).
+    /// 잘라낸 데모처럼 기준 패킷이 없어진 델타 대신 쓴다. 각 엔티티는 자기 베이스라인과 달라진 필드만 보낸다. 디코더가 없으면 None.
+    pub fn full_packet_entities(&self, aux: &Aux) -> Option<Vec<u8>> {
+        let mut bw = BitWriter::new();
+        let mut previous = 0;
+
+        for (&index, state) in &self.current.entities {
+            let difference = index.wrapping_sub(previous);
+            if difference == 1 {
+                bw.write_bit(true);
+            } else {
+                bw.write_bit(false);
+                // 0 번을 차이로 쓰면 목록 끝(16비트 0)과 구분되지 않으므로 절대 번호로 쓴다
+                if (1..64).contains(&difference) {
+                    bw.write_bit(false);
+                    bw.write_n(difference as u32, 6);
+                } else {
+                    bw.write_bit(true);
+                    bw.write_n(index as u32, 11);
+                }
+            }
+
+            bw.write_bit(state.custom);
+            if aux.instanced_baseline_count > 0 {
+                // 인스턴스 베이스라인을 쓰지 않는다
+                bw.write_bit(false);
+            }
+            // 같은 패킷의 앞 엔티티를 기준으로 삼지 않는다
+            bw.write_bit(false);
+
+            let decoder = aux
+                .delta_decoders
+                .get(entity_decoder_name(aux, index, state.custom))?;
+            let baseline = self.baseline(index, None);
+            write_delta(
+                &mut bw,
+                decoder,
+                &diff_delta(decoder, &baseline.fields, &state.fields),
+            );
+
+            previous = index;
+        }
+
+        bw.write_n(0, 16);
+
+        let mut bytes = vec![SVC_PACKETENTITIES];
+        bytes.extend((self.current.entities.len() as u16).to_le_bytes());
+        bytes.extend(bw.into_bytes());
+        Some(bytes)
+    }
+
+    /// 현재 clientdata_t 와 weapon_data_t 를 0 에서부터 보내는 svc_clientdata (메세지 번호 포함).
+    /// 디코더가 없으면 None.
+    pub fn full_client_data(&self, aux: &Aux) -> Option<Vec<u8>> {
+        let mut bytes = vec![SVC_CLIENTDATA];
+
+        // HLTV 는 본문이 없다
+        if aux.is_hltv {
+            return Some(bytes);
+        }
+
+        let client_decoder = aux.delta_decoders.get("clientdata_t")?;
+        let weapon_decoder = aux.delta_decoders.get("weapon_data_t")?;
+        let mut bw = BitWriter::new();
+
+        // 기준 패킷 없음
+        bw.write_bit(false);
+        write_delta(
+            &mut bw,
+            client_decoder,
+            &diff_delta(
+                client_decoder,
+                &Delta::new(),
+                &self.current.client_data.fields,
+            ),
+        );
+
+        for (&index, weapon) in &self.current.weapon_data {
+            bw.write_bit(true);
+            bw.write_n(index as u32, 6);
+            write_delta(
+                &mut bw,
+                weapon_decoder,
+                &diff_delta(weapon_decoder, &Delta::new(), &weapon.fields),
+            );
+        }
+        bw.write_bit(false);
+
+        bytes.extend(bw.into_bytes());
+        Some(bytes)
+    }
+
+    fn spawn_baseline(&mut self, message: &SvcSpawnBaseline) {
+        self.baselines = message
+            .entities
+            .iter()
+            .map(|entity| {
+                (
+                    entity.index,
+                    EntityState {
+                        fields: entity.delta.clone(),
+                        custom: entity.type_ & ENTITY_NORMAL == 0,
+                    },
+                )
+            })
+            .collect();
+
+        self.instanced_baselines = message
+            .extra_data
+            .iter()
+            .map(|delta| EntityState {
+                fields: delta.clone(),
+                custom: false,
+            })
+            .collect();
+    }
+
+    fn baseline(&self, entity_index: u16, baseline_index: Option<Option<u8>>) -> EntityState {
+        match baseline_index.flatten() {
+            Some(index) => self.instanced_baselines.get(index as usize),
+            None => self.baselines.get(&entity_index),
+        }
+        .cloned()
+        .unwrap_or_default()
+    }
+
+    /// 전체 갱신. 목록에 없는 엔티티는 사라진 것이다.
+    fn packet_entities(&mut self, message: &SvcPacketEntities) {
+        let mut entities: Vec<(u16, EntityState)> = Vec::with_capacity(message.entities.len());
+
+        for entity in &message.entities {
+            // offset 은 엔티티 번호가 아니라 이 패킷 목록 안에서의 거리다 (SV_FindBestBaseline)
+            let mut state = match entity.baseline_offset.flatten() {
+                Some(offset) => entities
+                    .len()
+                    .checked_sub(offset as usize)
+                    .and_then(|position| entities.get(position))
+                    .map(|(_, state)| state.clone())
+                    .unwrap_or_default(),
+                None => self.baseline(entity.entity_index, entity.baseline_index),
+            };
+
+            apply_delta(&mut state.fields, &entity.delta);
+            state.custom = entity.has_custom_delta;
+            entities.push((entity.entity_index, state));
+        }
+
+        self.current.entities = entities.into_iter().collect();
+    }
+
+    /// 기준 패킷에 대한 변경분. 목록에 없는 엔티티는 그대로 유지된다.
+    fn delta_packet_entities(&mut self, message: &SvcDeltaPacketEntities) {
+        let mut entities = self
+            .history
+            .get(&message.delta_sequence)
+            .unwrap_or(&self.current)
+            .entities
+            .clone();
+
+        for entity in &message.entities {
+            if entity.remove_entity {
+                entities.remove(&entity.entity_index);
+                continue;
+            }
+
+            let Some(delta) = &entity.delta else {
+                continue;
+            };
+
+            let state = entities
+                .entry(entity.entity_index)
+                .or_insert_with(|| self.baseline(entity.entity_index, entity.baseline_index));
+            apply_delta(&mut state.fields, delta);
+            state.custom = entity.has_custom_delta.unwrap_or(state.custom);
+        }
+
+        self.current.entities = entities;
+    }
+
+    /// 기준 패킷이 없으면 0 으로 채운 상태에서 시작한다 (CL_ParseClientdata)
+    fn client_data(&mut self, message: &SvcClientData) {
+        let (mut client_data, mut weapon_data) = match message.delta_update_mask {
+            Some(sequence) => {
+                let from = self.history.get(&sequence).unwrap_or(&self.current);
+                (from.client_data.clone(), from.weapon_data.clone())
+            }
+            None => Default::default(),
+        };
+
+        apply_delta(&mut client_data.fields, &message.client_data);
+
+        for weapon in &message.weapon_data {
+            let state = weapon_data.entry(weapon.weapon_index).or_default();
+            apply_delta(&mut state.fields, &weapon.weapon_data);
+        }
+
+        self.current.client_data = client_data;
+        self.current.weapon_data = weapon_data;
+    }
+}
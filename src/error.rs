@@ -0,0 +1,231 @@
+//! 데모 파싱 에러
+//!
+//! 프레임 단위 에러는 파일 안의 바이트 오프셋과 데모 전체에서의 프레임 순번을 함께 가진다.
+
+use std::{fmt, io};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DemoParseError {
+    /// HLDEMO 매직스트링이 없다
+    BadMagic,
+    /// 매직스트링은 맞지만 헤더 길이(544)보다 짧다
+    TruncatedHeader { length: usize },
+    UnsupportedVersion {
+        demo_protocol: i32,
+        network_protocol: i32,
+    },
+    /// 디렉토리 오프셋이 파일 밖이거나 엔트리를 읽을 수 없다
+    BadDirectory { offset: usize },
+    /// 프레임이 입력 끝에서 잘렸다
+    TruncatedFrame { offset: usize, frame_index: usize },
+    UnknownFrameType {
+        frame_type: u8,
+        offset: usize,
+        frame_index: usize,
+    },
+    /// 프레임 길이는 맞지만 내용(음수 길이, 네트워크 메세지 등)을 해석할 수 없다
+    InvalidFrame { offset: usize, frame_index: usize },
+}
+
+impl DemoParseError {
+    /// 에러가 난 위치. 헤더 에러는 0 이다.
+    pub fn offset(&self) -> usize {
+        match *self {
+            DemoParseError::BadMagic
+            | DemoParseError::TruncatedHeader { .. }
+            | DemoParseError::UnsupportedVersion { .. } => 0,
+            DemoParseError::BadDirectory { offset }
+            | DemoParseError::TruncatedFrame { offset, .. }
+            | DemoParseError::UnknownFrameType { offset, .. }
+            | DemoParseError::InvalidFrame { offset, .. } => offset,
+        }
+    }
+
+    /// 프레임 에러면 데모 처음부터 센 프레임 순번
+    pub fn frame_index(&self) -> Option<usize> {
+        match *self {
+            DemoParseError::TruncatedFrame { frame_index, .. }
+            | DemoParseError::UnknownFrameType { frame_index, .. }
+            | DemoParseError::InvalidFrame { frame_index, .. } => Some(frame_index),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DemoParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DemoParseError::BadMagic => write!(f, "Invalid Demo Header"),
+            DemoParseError::TruncatedHeader { length } => {
+                write!(f, "Truncated Demo Header ({} bytes)", length)
+            }
+            DemoParseError::UnsupportedVersion {
+                demo_protocol,
+                network_protocol,
+            } => write!(
+                f,
+                "Unsupported demo protocol {} (network protocol {})",
+                demo_protocol, network_protocol
+            ),
+            DemoParseError::BadDirectory { offset } => {
+                write!(f, "Invalid Demo Directory at offset {}", offset)
+            }
+            DemoParseError::TruncatedFrame {
+                offset,
+                frame_index,
+            } => write!(
+                f,
+                "Truncated Demo Frame #{} at offset {}",
+                frame_index, offset
+            ),
+            DemoParseError::UnknownFrameType {
+                frame_type,
+                offset,
+                frame_index,
+            } => write!(
+                f,
+                "Unknown frame type {} in frame #{} at offset {}",
+                frame_type, frame_index, offset
+            ),
+            DemoParseError::InvalidFrame {
+                offset,
+                frame_index,
+            } => write!(
+                f,
+                "Invalid Demo Frame #{} at offset {}",
+                frame_index, offset
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DemoParseError {}
+
+/// 경로나 스트림을 받는 함수는 io::Result 를 돌려주므로 InvalidData 로 감싼다.
+/// 원래 에러는 `get_ref()` 후 downcast 로 꺼낼 수 있다.
+impl From<DemoParseError> for io::Error {
+    fn from(error: DemoParseError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// lenient 모드에서 다음 프레임 헤더를 찾느라 건너뛴 구간
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedBytes {
+    /// 건너뛴 구간의 시작 (깨진 프레임의 시작)
+    pub offset: usize,
+    pub length: usize,
+    /// 건너뛰게 된 에러
+    pub error: DemoParseError,
+}
+
+/// 엄격하지 않은 모드에서 원본 파일과 달라진 부분
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recovery {
+    /// 깨진 프레임을 건너뛴 구간
+    pub skipped: Vec<SkippedBytes>,
+    /// 디렉토리가 없거나 깨져서 헤더 끝부터 프레임을 읽어 다시 만들었는지
+    pub directory_synthesized: bool,
+    /// 디렉토리를 다시 만들 때 마지막 완전한 프레임 뒤에 남아 버린 바이트 수
+    pub truncated_bytes: usize,
+}
+
+/// 데모 합치기 에러. `index` 는 입력 목록에서의 위치다.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeError {
+    /// 합칠 데모가 없다
+    Empty,
+    MapMismatch {
+        index: usize,
+        expected: String,
+        found: String,
+    },
+    CrcMismatch {
+        index: usize,
+        expected: u32,
+        found: u32,
+    },
+    /// svc_serverinfo 의 서버 이름이 다르다
+    HostnameMismatch {
+        index: usize,
+        expected: String,
+        found: String,
+    },
+    /// LOADING 세그먼트의 델타 디코더, 사용자 메세지 번호, 리소스, 베이스라인 중 `message` 가 다르다
+    LoadingMismatch { index: usize, message: &'static str },
+    /// `MsgDataParseMode::Raw` 로 읽지 않아 네트워크 메세지를 비교하거나 다시 쓸 수 없다
+    NotRaw { index: usize },
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::Empty => write!(f, "No demos to merge"),
+            MergeError::MapMismatch {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Demo #{} is recorded on {}, expected {}",
+                index, found, expected
+            ),
+            MergeError::CrcMismatch {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Demo #{} has map CRC {:#010x}, expected {:#010x}",
+                index, found, expected
+            ),
+            MergeError::HostnameMismatch {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Demo #{} is recorded on server {}, expected {}",
+                index, found, expected
+            ),
+            MergeError::LoadingMismatch { index, message } => write!(
+                f,
+                "Demo #{} has a different {} in its LOADING segment",
+                index, message
+            ),
+            MergeError::NotRaw { index } => write!(
+                f,
+                "Demo #{} network messages were not read in raw mode",
+                index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// 데모 자르기 에러
+#[derive(Debug, Clone, PartialEq)]
+pub enum CutError {
+    /// 시작 시각이 끝 시각보다 뒤거나 NaN 이다
+    InvalidRange { start: f32, end: f32 },
+    /// `MsgDataParseMode::Raw` 로 읽지 않아 네트워크 메세지를 다시 쓸 수 없다
+    NotRaw { frame: i32 },
+}
+
+impl fmt::Display for CutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CutError::InvalidRange { start, end } => {
+                write!(f, "Cut range {}..{} is empty", start, end)
+            }
+            CutError::NotRaw { frame } => write!(
+                f,
+                "Network message at frame {} was not read in raw mode",
+                frame
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CutError {}
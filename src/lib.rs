@@ -0,0 +1,23 @@
+pub mod analyze; //데모 분석모듈
+pub mod anonymize; //데모 익명화 (이름, 채팅, SteamID)
+pub mod bitstream; //비트 스트림 리더
+pub mod bspfile; //bsp 구조체 파싱모듈
+pub mod commands; //콘솔 커멘드 타임라인 (키 입력, cvar)
+pub mod delta; //델타 압축 디코더
+pub mod demo; //데모 파싱모듈
+pub mod edit; //데모 편집 (자르기, 합치기)
+pub mod entity; //엔티티 상태 추적
+pub mod error; //데모 파싱 에러
+pub mod nom_helper; //nom 공용 헬퍼
+pub mod parse; //nom 기반 데모 파서
+pub mod parse_netmsg; //네트워크 메세지 파서
+pub mod playback; //이벤트 프레임 타임라인 (발사, 소음기)
+pub mod player; //플레이어 추적
+pub mod protocol; //프로토콜 버전과 모드 구분
+pub mod reader; //스트리밍 데모 리더
+pub mod sound; //사운드 프레임 타임라인 (발소리, 착지)
+pub mod render; //렌더링 모듈
+pub mod types; //데모 구조체
+pub mod usermsg; //CS 사용자 메세지
+pub mod weapon; //CS 무기 구분과 현재 무기 추적
+pub mod write; //데모 파일 쓰기
@@ -1,13 +1,7 @@
-mod analyze; //데모 분석모듈
-mod bspfile; //bsp 구조체 파싱모듈
-mod demo; //데모 파싱모듈
-mod parse;
-mod render; //렌더링 모듈
-
-use analyze::*;
-use bspfile::load_bsp_file;
-use demo::parse;
-use render::{render_jump_cross_section, render_jump_gif, render_slice_image};
+use hello::analyze::*;
+use hello::bspfile::load_bsp_file;
+use hello::demo::parse;
+use hello::render::{render_jump_cross_section, render_jump_gif, render_slice_image};
 
 fn main() {
     // 1. 맵 로드
@@ -30,8 +24,18 @@ fn main() {
         }
     };
 
-    // 3. 점프 세그먼트 추출
-    let segments: Vec<JumpSegment> = extract_jump_segments(&parsed, &map_data);
+    println!(
+        "Demo map: {}, duration: {:.2}s",
+        parsed.header.map_name,
+        parsed.duration()
+    );
+
+    // 3. 점프 세그먼트 추출 (관전 데모는 관전 대상 구간별로)
+    let movement_frames = parsed.movement_frames();
+    let segments: Vec<JumpSegment> = movement_frames
+        .iter()
+        .flat_map(|frames| extract_jump_segments(frames, &map_data))
+        .collect();
     println!("Detected jump segments: {}", segments.len());
 
     // 4. 첫 번째 세그먼트를 대상으로 PNG + GIF 테스트 렌더링
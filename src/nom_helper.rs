@@ -0,0 +1,44 @@
+//! nom 파서 공용 헬퍼
+
+use nom::{
+    IResult,
+    bytes::complete::{tag, take, take_until},
+    combinator::map,
+    error::{Error, ErrorKind},
+    number::complete::{le_f32, le_i16, le_i32},
+    sequence::{terminated, tuple},
+};
+
+pub type Result<'a, T> = IResult<&'a [u8], T>;
+
+/// 복구하지 않는 실패를 반환한다.
+pub fn nom_fail<T>(i: &[u8]) -> Result<'_, T> {
+    Err(nom::Err::Failure(Error::new(i, ErrorKind::Fail)))
+}
+
+/// float 3개로 이루어진 벡터
+pub fn take_point_float(i: &[u8]) -> Result<'_, [f32; 3]> {
+    map(tuple((le_f32, le_f32, le_f32)), |(x, y, z)| [x, y, z])(i)
+}
+
+/// int 3개로 이루어진 벡터
+pub fn take_point_int(i: &[u8]) -> Result<'_, [i32; 3]> {
+    map(tuple((le_i32, le_i32, le_i32)), |(x, y, z)| [x, y, z])(i)
+}
+
+/// MSG_WriteCoord 로 기록된 short 3개
+pub fn take_point_coord(i: &[u8]) -> Result<'_, [i16; 3]> {
+    map(tuple((le_i16, le_i16, le_i16)), |(x, y, z)| [x, y, z])(i)
+}
+
+/// 고정 길이 바이트를 그대로 복사한다.
+pub fn take_bytes(n: usize) -> impl Fn(&[u8]) -> Result<'_, Vec<u8>> {
+    move |i| map(take(n), |b: &[u8]| b.to_vec())(i)
+}
+
+/// 널 종료 문자열. 반환값에 널 문자는 포함하지 않는다.
+pub fn null_string(i: &[u8]) -> Result<'_, Vec<u8>> {
+    map(terminated(take_until(&b"\0"[..]), tag(b"\0")), |s: &[u8]| {
+        s.to_vec()
+    })(i)
+}
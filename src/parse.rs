@@ -3,28 +3,52 @@ use std::{ffi::OsStr, fs::OpenOptions, io::Read, path::Path};
 use nom::{
     bytes::complete::take,
     combinator::{map, verify},
-    multi::{count, many_till, many0},
     number::complete::{le_f32, le_i8, le_i16, le_i32, le_u8, le_u16, le_u32},
     sequence::tuple,
 };
 
 use crate::{
-    nom_helper::{Result, nom_fail, take_point_float},
-    parse_netmsg,
+    error::{DemoParseError, Recovery, SkippedBytes},
+    nom_helper::{Result, nom_fail, take_bytes, take_point_float},
+    parse_netmsg::parse_netmsg,
+    protocol::{DEMO_PROTOCOL, ProtocolLayout},
     types::{
         Aux, AuxRefCell, ClientData, ConsoleCommand, Demo, DemoBuffer, DemoInfo, Directory,
-        DirectoryEntry, Event, EventArgs, Frame, FrameData, Header, MessageData,
-        MessageDataParseMode, MoveVars, NetworkMessage, NetworkMessageType, RefParams,
-        SequenceInfo, Sound, UserCmd, WeaponAnimation,
+        DirectoryEntry, Event, EventArgs, Frame, FrameData, Header, MessageData, MoveVars,
+        NetworkMessage, NetworkMessageType, RefParams, SequenceInfo, Sound, UserCmd,
+        WeaponAnimation,
     },
 };
 
+/// 네트워크 메세지 페이로드 처리 방식
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MsgDataParseMode {
+    /// svc_* 메세지 단위로 파싱
     Parse,
+    /// 원본 바이트만 보관
     Raw,
+    /// 버림
     None,
 }
 
+/// 깨진 프레임을 만났을 때의 처리 방식
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorMode {
+    /// 첫 에러에서 멈춘다
+    Strict,
+    /// 다음 프레임 헤더를 찾아 이어서 읽고, 건너뛴 구간을 기록한다
+    Lenient,
+    /// Lenient 에 더해, 디렉토리가 없거나 깨졌으면 헤더 끝부터 프레임을 읽어 디렉토리를 다시 만든다
+    Recover,
+}
+
+pub const HEADER_SIZE: usize = 544;
+pub const DIRECTORY_ENTRY_SIZE: usize = 92;
+/// 프레임 타입, 시간, 프레임 번호
+pub const FRAME_HEADER_SIZE: usize = 9;
+/// 네트워크 메세지 프레임에서 msg_length 까지의 고정 길이
+pub const NETWORK_MESSAGE_HEADER_SIZE: usize = 468;
+
 impl Demo {
     pub fn parse_from_file(
         path: impl AsRef<OsStr> + AsRef<Path>,
@@ -34,6 +58,887 @@ impl Demo {
         let mut bytes: Vec<u8> = vec![];
 
         file.read_to_end(&mut bytes)?;
+
+        match parse_demo_checked(&bytes, mode, ErrorMode::Strict) {
+            Ok((demo, _)) => Ok(demo),
+            Err(err) => Err(eyre::eyre!("Cannot parse demo: {}", err)),
+        }
+    }
+}
+
+pub fn parse_demo(i: &[u8], parse_mode: MsgDataParseMode) -> Result<'_, Demo> {
+    match parse_demo_checked(i, parse_mode, ErrorMode::Strict) {
+        Ok((demo, _)) => Ok((&i[i.len()..], demo)),
+        Err(_) => nom_fail(i),
+    }
+}
+
+/// `parse_demo` 와 같지만 실패한 위치를 DemoParseError 로 돌려준다.
+/// 원본과 달라진 부분은 Recovery 로 함께 돌려준다. Strict 모드에서는 항상 비어 있다.
+pub fn parse_demo_checked(
+    i: &[u8],
+    parse_mode: MsgDataParseMode,
+    error_mode: ErrorMode,
+) -> std::result::Result<(Demo, Recovery), DemoParseError> {
+    let header = parse_header_checked(i)?;
+    let mut recovery = Recovery::default();
+
+    let (mut directory, input_end) = match parse_directory_checked(i, &header) {
+        Ok(directory) => (directory, i.len()),
+        Err(_) if error_mode == ErrorMode::Recover => {
+            let (directory, end) = synthesize_directory(i);
+            recovery.directory_synthesized = true;
+            recovery.truncated_bytes = i.len() - end;
+            (directory, end)
+        }
+        Err(error) => return Err(error),
+    };
+
+    // 세그먼트는 다음 세그먼트나 디렉토리가 시작하는 곳을 넘지 않는다
+    let mut boundaries: Vec<usize> = directory
+        .entries
+        .iter()
+        .map(|entry| entry.offset as usize)
+        .chain([header.directory_offset as usize, input_end])
+        .collect();
+    boundaries.sort_unstable();
+
+    let mut walker = FrameWalker {
+        input: i,
+        parse_mode,
+        error_mode,
+        // LOADING 세그먼트에서 등록된 사용자 메세지를 이후 세그먼트에서도 써야 하므로 공유한다.
+        aux: Aux::new_ref_cell(),
+        frame_index: 0,
+        skipped: vec![],
+    };
+
+    for entry in directory.entries.iter_mut() {
+        let start = entry.offset as usize;
+        let end = boundaries
+            .iter()
+            .find(|&&boundary| boundary > start)
+            .map_or(input_end, |&end| end.min(input_end));
+
+        let (frames, frames_end) = walker.segment(start, end)?;
+        entry.frames = frames;
+        entry.trailing = i[frames_end..end].to_vec();
+    }
+
+    if recovery.directory_synthesized {
+        fill_synthesized_entries(&mut directory);
+    }
+    recovery.skipped = walker.skipped;
+
+    Ok((Demo { header, directory }, recovery))
+}
+
+/// 디렉토리 없이 헤더 끝부터 프레임 길이만 따라가며 세그먼트를 나눈다.
+/// 세그먼트는 NextSection 에서 끝나고 다음 DemoStart 에서 시작한다. 그 사이의 프레임은 버린다.
+/// 마지막 세그먼트가 끝나는 위치를 함께 돌려준다.
+fn synthesize_directory(i: &[u8]) -> (Directory, usize) {
+    let mut entries = vec![];
+    let mut segment_start = Some(HEADER_SIZE);
+    let mut offset = HEADER_SIZE;
+    let mut end = HEADER_SIZE;
+
+    while let Ok(length) = frame_length(&i[offset..], offset, 0) {
+        let frame_type = i[offset];
+
+        if frame_type == 2 {
+            if let Some(start) = segment_start.filter(|&start| start < offset) {
+                entries.push(synthesized_entry(entries.len(), start, offset));
+                end = offset;
+            }
+            segment_start = Some(offset);
+        }
+
+        offset += length;
+
+        if frame_type == 5
+            && let Some(start) = segment_start.take()
+        {
+            entries.push(synthesized_entry(entries.len(), start, offset));
+            end = offset;
+        }
+    }
+
+    // 디렉토리가 남아 있으면 엔트리 수(2)가 DemoStart 프레임 하나로 읽히므로
+    // DemoStart 뿐인 마지막 세그먼트는 버린다
+    if let Some(start) = segment_start
+        .filter(|&start| start < offset && !(i[start] == 2 && offset - start == FRAME_HEADER_SIZE))
+    {
+        entries.push(synthesized_entry(entries.len(), start, offset));
+        end = offset;
+    }
+
+    (Directory { entries }, end)
+}
+
+/// 엔진처럼 첫 세그먼트는 LOADING, 나머지는 Playback 으로 둔다.
+fn synthesized_entry(index: usize, start: usize, end: usize) -> DirectoryEntry {
+    let (type_, name) = if index == 0 {
+        (0, &b"LOADING"[..])
+    } else {
+        (1, &b"Playback"[..])
+    };
+    let mut description = name.to_vec();
+    description.resize(64, 0);
+
+    DirectoryEntry {
+        type_,
+        description,
+        flags: 0,
+        cd_track: -1,
+        track_time: 0.0,
+        frame_count: 0,
+        offset: start as i32,
+        file_length: (end - start) as i32,
+        frames: vec![],
+        trailing: vec![],
+    }
+}
+
+/// 엔진은 LOADING 엔트리의 시간과 프레임 수를 0 으로 남기고,
+/// Playback 엔트리에는 마지막 프레임의 시간과 프레임 번호 + 1 을 기록한다.
+fn fill_synthesized_entries(directory: &mut Directory) {
+    for entry in &mut directory.entries {
+        if entry.type_ != 0
+            && let Some(last) = entry.frames.last()
+        {
+            entry.track_time = last.time;
+            entry.frame_count = last.frame + 1;
+        }
+    }
+}
+
+/// 매직스트링과 헤더 길이만 확인한다. 프로토콜은 `check_protocol` 로 따로 확인한다.
+pub fn parse_header_checked(i: &[u8]) -> std::result::Result<Header, DemoParseError> {
+    if !i.starts_with(b"HLDEMO") {
+        return Err(DemoParseError::BadMagic);
+    }
+
+    match parse_header(i) {
+        Ok((_, header)) => Ok(header),
+        Err(_) => Err(DemoParseError::TruncatedHeader { length: i.len() }),
+    }
+}
+
+/// 이 파서가 읽을 수 있는 데모/네트워크 프로토콜인지 확인하고 메세지 배치를 돌려준다.
+pub fn check_protocol(header: &Header) -> std::result::Result<ProtocolLayout, DemoParseError> {
+    ProtocolLayout::new(header.network_protocol)
+        .filter(|_| header.demo_protocol == DEMO_PROTOCOL)
+        .ok_or(DemoParseError::UnsupportedVersion {
+            demo_protocol: header.demo_protocol,
+            network_protocol: header.network_protocol,
+        })
+}
+
+/// 헤더의 디렉토리 오프셋에서 디렉토리를 읽는다. 엔트리의 오프셋도 파일 안에 있어야 한다.
+pub fn parse_directory_checked(
+    i: &[u8],
+    header: &Header,
+) -> std::result::Result<Directory, DemoParseError> {
+    let error = DemoParseError::BadDirectory {
+        offset: header.directory_offset.max(0) as usize,
+    };
+
+    let directory = usize::try_from(header.directory_offset)
+        .ok()
+        .filter(|&offset| offset >= HEADER_SIZE)
+        .and_then(|offset| i.get(offset..))
+        .and_then(|i| parse_directory(i).ok())
+        .map(|(_, directory)| directory)
+        .ok_or_else(|| error.clone())?;
+
+    // 쓰레기 값을 디렉토리로 읽었다면 엔트리 오프셋이 프레임 시작이 아닐 것이다
+    let offsets_are_frames = directory.entries.iter().all(|entry| {
+        usize::try_from(entry.offset)
+            .ok()
+            .filter(|&offset| offset >= HEADER_SIZE)
+            .and_then(|offset| i.get(offset..))
+            .is_some_and(|frame| frame_length(frame, 0, 0).is_ok())
+    });
+    if !offsets_are_frames {
+        return Err(error);
+    }
+
+    Ok(directory)
+}
+
+/// 세그먼트를 차례로 읽으면서 데모 전체의 프레임 순번과 건너뛴 구간을 센다.
+struct FrameWalker<'a> {
+    input: &'a [u8],
+    parse_mode: MsgDataParseMode,
+    error_mode: ErrorMode,
+    aux: AuxRefCell,
+    frame_index: usize,
+    skipped: Vec<SkippedBytes>,
+}
+
+impl FrameWalker<'_> {
+    /// `start` 부터 NextSection 프레임 또는 `end` 까지 프레임을 읽는다.
+    /// 마지막으로 읽은 프레임이 끝나는 위치를 함께 돌려준다.
+    fn segment(
+        &mut self,
+        start: usize,
+        end: usize,
+    ) -> std::result::Result<(Vec<Frame>, usize), DemoParseError> {
+        let mut offset = start;
+        let mut frames: Vec<Frame> = vec![];
+
+        while offset < end {
+            let (length, frame) = match self.frame(offset, end) {
+                Ok(frame) => frame,
+                Err(error) if self.error_mode != ErrorMode::Strict => {
+                    let next = (offset + 1..end)
+                        .find(|&next| is_frame_header(&self.input[next..end], frames.last()))
+                        .unwrap_or(end);
+                    self.skip(offset, next - offset, error);
+                    offset = next;
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+
+            offset += length;
+            self.frame_index += 1;
+
+            let is_last = matches!(frame.frame_data, FrameData::NextSection);
+            frames.push(frame);
+
+            if is_last {
+                break;
+            }
+        }
+
+        Ok((frames, offset))
+    }
+
+    fn frame(
+        &self,
+        offset: usize,
+        end: usize,
+    ) -> std::result::Result<(usize, Frame), DemoParseError> {
+        let i = &self.input[offset..end];
+        let length = frame_length(i, offset, self.frame_index)?;
+
+        match parse_frame(&i[..length], self.parse_mode, &self.aux) {
+            Ok((_, frame)) => Ok((length, frame)),
+            Err(_) => Err(DemoParseError::InvalidFrame {
+                offset,
+                frame_index: self.frame_index,
+            }),
+        }
+    }
+
+    /// 찾은 헤더가 다시 깨져 있으면 바로 이어지는 구간이므로 하나로 합친다.
+    fn skip(&mut self, offset: usize, length: usize, error: DemoParseError) {
+        if let Some(last) = self.skipped.last_mut()
+            && last.offset + last.length == offset
+        {
+            last.length += length;
+            return;
+        }
+
+        self.skipped.push(SkippedBytes {
+            offset,
+            length,
+            error,
+        });
+    }
+}
+
+/// 프레임 헤더와 길이 필드만 보고 프레임 전체 길이를 구한다.
+fn frame_length(
+    i: &[u8],
+    offset: usize,
+    frame_index: usize,
+) -> std::result::Result<usize, DemoParseError> {
+    let truncated = DemoParseError::TruncatedFrame {
+        offset,
+        frame_index,
+    };
+    let Some(&frame_type) = i.first() else {
+        return Err(truncated);
+    };
+
+    // at 위치의 i32 길이 필드
+    let length_at = |at: usize| match i.get(at..at + 4) {
+        Some(bytes) => {
+            usize::try_from(i32::from_le_bytes(bytes.try_into().unwrap())).map_err(|_| {
+                DemoParseError::InvalidFrame {
+                    offset,
+                    frame_index,
+                }
+            })
+        }
+        None => Err(truncated.clone()),
+    };
+
+    let body = match frame_type {
+        0 | 1 => {
+            NETWORK_MESSAGE_HEADER_SIZE
+                + length_at(FRAME_HEADER_SIZE + NETWORK_MESSAGE_HEADER_SIZE - 4)?
+        }
+        2 | 5 => 0,
+        3 => 64,
+        4 => 32,
+        6 => 84,
+        7 => 8,
+        8 => 8 + length_at(FRAME_HEADER_SIZE + 4)? + 16,
+        9 => 4 + length_at(FRAME_HEADER_SIZE)?,
+        frame_type => {
+            return Err(DemoParseError::UnknownFrameType {
+                frame_type,
+                offset,
+                frame_index,
+            });
+        }
+    };
+
+    let length = FRAME_HEADER_SIZE + body;
+    if length > i.len() {
+        return Err(truncated);
+    }
+
+    Ok(length)
+}
+
+/// Lenient 모드에서 찾은 헤더 뒤로 이만큼의 프레임 헤더가 더 이어져야 프레임 시작으로 본다.
+/// 네트워크 메세지 안의 바이트가 우연히 프레임 헤더 하나처럼 보이는 경우를 거른다.
+const RESYNC_CHAIN: usize = 2;
+
+/// Lenient 모드에서 프레임 시작으로 볼 수 있는 위치인지 확인한다.
+/// 직전 프레임과 시간, 프레임 번호가 이어지고, 길이가 입력 안에 들어가며,
+/// 뒤따르는 `RESYNC_CHAIN` 개의 프레임도 같은 조건을 만족하거나 입력 끝이어야 한다.
+fn is_frame_header(i: &[u8], last: Option<&Frame>) -> bool {
+    is_frame_chain(i, last.map(|last| (last.time, last.frame)), RESYNC_CHAIN)
+}
+
+/// `last` 는 직전 프레임의 시간과 프레임 번호다.
+fn is_frame_chain(i: &[u8], last: Option<(f32, i32)>, chain: usize) -> bool {
+    let Ok((_, (frame_type, time, frame))) = parse_frame_header(i) else {
+        return false;
+    };
+
+    if frame_type > 9 || !time.is_finite() {
+        return false;
+    }
+
+    let continues = match last {
+        Some((last_time, last_frame)) => {
+            (last_time - 1.0..=last_time + 60.0).contains(&time)
+                && (last_frame..=last_frame.saturating_add(10_000)).contains(&frame)
+        }
+        None => time >= 0.0 && frame >= 0,
+    };
+    if !continues {
+        return false;
     }
+
+    let Ok(length) = frame_length(i, 0, 0) else {
+        return false;
+    };
+
+    match &i[length..] {
+        [] => true,
+        next if chain == 0 => next[0] <= 9,
+        next => is_frame_chain(next, Some((time, frame)), chain - 1),
+    }
+}
+
+pub fn parse_header(i: &[u8]) -> Result<'_, Header> {
+    map(
+        tuple((
+            verify(take_bytes(8), |magic: &[u8]| magic.starts_with(b"HLDEMO")),
+            le_i32,
+            le_i32,
+            take_bytes(260),
+            take_bytes(260),
+            le_u32,
+            le_i32,
+        )),
+        |(
+            magic,
+            demo_protocol,
+            network_protocol,
+            map_name,
+            game_directory,
+            map_checksum,
+            directory_offset,
+        )| Header {
+            magic,
+            demo_protocol,
+            network_protocol,
+            map_name,
+            game_directory,
+            map_checksum,
+            directory_offset,
+        },
+    )(i)
+}
+
+pub fn parse_directory(i: &[u8]) -> Result<'_, Directory> {
+    let (mut i, entry_count) = le_i32(i)?;
+
+    // 잘못된 오프셋을 읽었을 때 거대한 할당을 막는다.
+    if entry_count < 0 || entry_count as usize * DIRECTORY_ENTRY_SIZE > i.len() {
+        return nom_fail(i);
+    }
+
+    let mut entries = Vec::with_capacity(entry_count as usize);
+    for _ in 0..entry_count {
+        let (rest, entry) = parse_directory_entry(i)?;
+        entries.push(entry);
+        i = rest;
+    }
+
+    Ok((i, Directory { entries }))
+}
+
+fn parse_directory_entry(i: &[u8]) -> Result<'_, DirectoryEntry> {
+    map(
+        tuple((
+            le_i32,
+            take_bytes(64),
+            le_i32,
+            le_i32,
+            le_f32,
+            le_i32,
+            le_i32,
+            le_i32,
+        )),
+        |(type_, description, flags, cd_track, track_time, frame_count, offset, file_length)| {
+            DirectoryEntry {
+                type_,
+                description,
+                flags,
+                cd_track,
+                track_time,
+                frame_count,
+                offset,
+                file_length,
+                frames: vec![],
+                trailing: vec![],
+            }
+        },
+    )(i)
+}
+
+/// NextSection 프레임 또는 입력 끝까지 프레임을 읽는다.
+pub fn parse_frames<'a>(
+    i: &'a [u8],
+    parse_mode: MsgDataParseMode,
+    aux: &AuxRefCell,
+) -> Result<'a, Vec<Frame>> {
+    let mut i = i;
+    let mut frames = vec![];
+
+    while !i.is_empty() {
+        let (rest, frame) = parse_frame(i, parse_mode, aux)?;
+        i = rest;
+
+        let is_last = matches!(frame.frame_data, FrameData::NextSection);
+        frames.push(frame);
+
+        if is_last {
+            break;
+        }
+    }
+
+    Ok((i, frames))
+}
+
+pub fn parse_frame<'a>(
+    i: &'a [u8],
+    parse_mode: MsgDataParseMode,
+    aux: &AuxRefCell,
+) -> Result<'a, Frame> {
+    let (i, (frame_type, time, frame)) = parse_frame_header(i)?;
+
+    let (i, frame_data) = match frame_type {
+        0 => map(
+            |i| parse_network_message(i, parse_mode, aux),
+            |message| {
+                FrameData::NetworkMessage(Box::new((NetworkMessageType::Start, message)))
+            },
+        )(i)?,
+        1 => map(
+            |i| parse_network_message(i, parse_mode, aux),
+            |message| {
+                FrameData::NetworkMessage(Box::new((NetworkMessageType::Normal, message)))
+            },
+        )(i)?,
+        2 => (i, FrameData::DemoStart),
+        3 => map(parse_console_command, FrameData::ConsoleCommand)(i)?,
+        4 => map(parse_client_data, FrameData::ClientData)(i)?,
+        5 => (i, FrameData::NextSection),
+        6 => map(parse_event, FrameData::Event)(i)?,
+        7 => map(parse_weapon_animation, FrameData::WeaponAnimation)(i)?,
+        8 => map(parse_sound, FrameData::Sound)(i)?,
+        9 => map(parse_demo_buffer, FrameData::DemoBuffer)(i)?,
+        _ => return nom_fail(i),
+    };
+
+    Ok((
+        i,
+        Frame {
+            time,
+            frame,
+            frame_data,
+        },
+    ))
+}
+
+/// 프레임 타입, 시간, 프레임 번호
+fn parse_frame_header(i: &[u8]) -> Result<'_, (u8, f32, i32)> {
+    tuple((le_u8, le_f32, le_i32))(i)
+}
+
+fn parse_network_message<'a>(
+    i: &'a [u8],
+    parse_mode: MsgDataParseMode,
+    aux: &AuxRefCell,
+) -> Result<'a, NetworkMessage> {
+    let (i, (info, sequence_info, message_length)) =
+        tuple((parse_demo_info, parse_sequence_info, le_i32))(i)?;
+
+    if message_length < 0 {
+        return nom_fail(i);
+    }
+    let (i, message) = take(message_length as usize)(i)?;
+
+    aux.borrow_mut().is_hltv = info.ref_params.spectator != 0;
+
+    let messages = match parse_mode {
+        MsgDataParseMode::Parse => MessageData::Parse(parse_netmsg(message, aux)?.1),
+        MsgDataParseMode::Raw => MessageData::Raw(message.to_vec()),
+        MsgDataParseMode::None => MessageData::None,
+    };
+
+    Ok((
+        i,
+        NetworkMessage {
+            info,
+            sequence_info,
+            message_length,
+            messages,
+        },
+    ))
+}
+
+fn parse_demo_info(i: &[u8]) -> Result<'_, DemoInfo> {
+    map(
+        tuple((
+            le_f32,
+            parse_ref_params,
+            parse_user_cmd,
+            parse_movevars,
+            take_point_float,
+            le_i32,
+        )),
+        |(timestamp, ref_params, user_cmd, movevars, view, viewmodel)| DemoInfo {
+            timestamp,
+            ref_params,
+            user_cmd,
+            movevars,
+            view,
+            viewmodel,
+        },
+    )(i)
+}
+
+fn parse_ref_params(i: &[u8]) -> Result<'_, RefParams> {
+    let (i, (vieworg, viewangles, forward, right, up, frametime, time)) = tuple((
+        take_point_float,
+        take_point_float,
+        take_point_float,
+        take_point_float,
+        take_point_float,
+        le_f32,
+        le_f32,
+    ))(i)?;
+
+    let (i, (intermission, paused, spectator, onground, waterlevel)) =
+        tuple((le_i32, le_i32, le_i32, le_i32, le_i32))(i)?;
+
+    let (i, (simvel, simorg, viewheight, idealpitch, cl_viewangles, health, crosshairangle)) =
+        tuple((
+            take_point_float,
+            take_point_float,
+            take_point_float,
+            le_f32,
+            take_point_float,
+            le_i32,
+            take_point_float,
+        ))(i)?;
+
+    let (i, (viewsize, punchangle, maxclients, viewentity, playernum, max_entities)) =
+        tuple((le_f32, take_point_float, le_i32, le_i32, le_i32, le_i32))(i)?;
+
+    let (i, (demoplayback, hardware, smoothing, ptr_cmd, ptr_movevars)) =
+        tuple((le_i32, le_i32, le_i32, le_i32, le_i32))(i)?;
+
+    let (i, (viewport_x, viewport_y, viewport_w, viewport_h, next_view, only_client_draw)) =
+        tuple((le_i32, le_i32, le_i32, le_i32, le_i32, le_i32))(i)?;
+
+    Ok((
+        i,
+        RefParams {
+            vieworg,
+            viewangles,
+            forward,
+            right,
+            up,
+            frametime,
+            time,
+            intermission,
+            paused,
+            spectator,
+            onground,
+            waterlevel,
+            simvel,
+            simorg,
+            viewheight,
+            idealpitch,
+            cl_viewangles,
+            health,
+            crosshairangle,
+            viewsize,
+            punchangle,
+            maxclients,
+            viewentity,
+            playernum,
+            max_entities,
+            demoplayback,
+            hardware,
+            smoothing,
+            ptr_cmd,
+            ptr_movevars,
+            viewport: [viewport_x, viewport_y, viewport_w, viewport_h],
+            next_view,
+            only_client_draw,
+        },
+    ))
+}
+
+fn parse_user_cmd(i: &[u8]) -> Result<'_, UserCmd> {
+    let (i, (lerp_msec, msec, align_1, viewangles, forwardmove, sidemove, upmove)) =
+        tuple((
+            le_i16,
+            le_u8,
+            le_u8,
+            take_point_float,
+            le_f32,
+            le_f32,
+            le_f32,
+        ))(i)?;
+
+    let (
+        i,
+        (lightlevel, align_2, buttons, impulse, weaponselect, align_3, align_4),
+    ) = tuple((le_i8, le_u8, le_u16, le_i8, le_i8, le_u8, le_u8))(i)?;
+
+    let (i, (impact_index, impact_position)) = tuple((le_i32, take_point_float))(i)?;
+
+    Ok((
+        i,
+        UserCmd {
+            lerp_msec,
+            msec,
+            align_1,
+            viewangles,
+            forwardmove,
+            sidemove,
+            upmove,
+            lightlevel,
+            align_2,
+            buttons,
+            impulse,
+            weaponselect,
+            align_3,
+            align_4,
+            impact_index,
+            impact_position,
+        },
+    ))
+}
+
+fn parse_movevars(i: &[u8]) -> Result<'_, MoveVars> {
+    let (
+        i,
+        (gravity, stopspeed, maxspeed, spectatormaxspeed, accelerate, airaccelerate, wateraccelerate, friction),
+    ) = tuple((le_f32, le_f32, le_f32, le_f32, le_f32, le_f32, le_f32, le_f32))(i)?;
+
+    let (
+        i,
+        (edgefriction, waterfriction, entgravity, bounce, stepsize, maxvelocity, zmax, wave_height),
+    ) = tuple((le_f32, le_f32, le_f32, le_f32, le_f32, le_f32, le_f32, le_f32))(i)?;
+
+    let (i, (footsteps, sky_name, rollangle, rollspeed)) =
+        tuple((le_i32, take_bytes(32), le_f32, le_f32))(i)?;
+
+    let (i, (skycolor_r, skycolor_g, skycolor_b, skyvec)) =
+        tuple((le_f32, le_f32, le_f32, take_point_float))(i)?;
+
+    Ok((
+        i,
+        MoveVars {
+            gravity,
+            stopspeed,
+            maxspeed,
+            spectatormaxspeed,
+            accelerate,
+            airaccelerate,
+            wateraccelerate,
+            friction,
+            edgefriction,
+            waterfriction,
+            entgravity,
+            bounce,
+            stepsize,
+            maxvelocity,
+            zmax,
+            wave_height,
+            footsteps,
+            sky_name,
+            rollangle,
+            rollspeed,
+            skycolor_r,
+            skycolor_g,
+            skycolor_b,
+            skyvec,
+        },
+    ))
+}
+
+fn parse_sequence_info(i: &[u8]) -> Result<'_, SequenceInfo> {
+    map(
+        tuple((le_i32, le_i32, le_i32, le_i32, le_i32, le_i32, le_i32)),
+        |(
+            incoming_sequence,
+            incoming_acknowledged,
+            incoming_reliable_acknowledged,
+            incoming_reliable_sequence,
+            outgoing_sequence,
+            reliable_sequence,
+            last_reliable_sequence,
+        )| SequenceInfo {
+            incoming_sequence,
+            incoming_acknowledged,
+            incoming_reliable_acknowledged,
+            incoming_reliable_sequence,
+            outgoing_sequence,
+            reliable_sequence,
+            last_reliable_sequence,
+        },
+    )(i)
+}
+
+fn parse_console_command(i: &[u8]) -> Result<'_, ConsoleCommand> {
+    map(take_bytes(64), |command| ConsoleCommand { command })(i)
+}
+
+fn parse_client_data(i: &[u8]) -> Result<'_, ClientData> {
+    map(
+        tuple((take_point_float, take_point_float, le_i32, le_f32)),
+        |(origin, viewangles, weapon_bits, fov)| ClientData {
+            origin,
+            viewangles,
+            weapon_bits,
+            fov,
+        },
+    )(i)
+}
+
+fn parse_event(i: &[u8]) -> Result<'_, Event> {
+    map(
+        tuple((le_i32, le_i32, le_f32, parse_event_args)),
+        |(flags, index, delay, args)| Event {
+            flags,
+            index,
+            delay,
+            args,
+        },
+    )(i)
+}
+
+fn parse_event_args(i: &[u8]) -> Result<'_, EventArgs> {
+    let (i, (flags, entity_index, origin, angles, velocity, ducking)) = tuple((
+        le_i32,
+        le_i32,
+        take_point_float,
+        take_point_float,
+        take_point_float,
+        le_i32,
+    ))(i)?;
+
+    let (i, (fparam1, fparam2, iparam1, iparam2, bparam1, bparam2)) =
+        tuple((le_f32, le_f32, le_i32, le_i32, le_i32, le_i32))(i)?;
+
+    Ok((
+        i,
+        EventArgs {
+            flags,
+            entity_index,
+            origin,
+            angles,
+            velocity,
+            ducking,
+            fparam1,
+            fparam2,
+            iparam1,
+            iparam2,
+            bparam1,
+            bparam2,
+        },
+    ))
+}
+
+fn parse_weapon_animation(i: &[u8]) -> Result<'_, WeaponAnimation> {
+    map(tuple((le_i32, le_i32)), |(anim, body)| WeaponAnimation {
+        anim,
+        body,
+    })(i)
+}
+
+fn parse_sound(i: &[u8]) -> Result<'_, Sound> {
+    let (i, (channel, sample_length)) = tuple((le_i32, le_i32))(i)?;
+
+    if sample_length < 0 {
+        return nom_fail(i);
+    }
+
+    let (i, (sample, volume, attenuation, flags, pitch)) = tuple((
+        take_bytes(sample_length as usize),
+        le_f32,
+        le_f32,
+        le_i32,
+        le_i32,
+    ))(i)?;
+
+    Ok((
+        i,
+        Sound {
+            channel,
+            sample,
+            volume,
+            attenuation,
+            flags,
+            pitch,
+        },
+    ))
+}
+
+fn parse_demo_buffer(i: &[u8]) -> Result<'_, DemoBuffer> {
+    let (i, buffer_length) = le_i32(i)?;
+
+    if buffer_length < 0 {
+        return nom_fail(i);
+    }
+
+    map(take_bytes(buffer_length as usize), |buffer| DemoBuffer {
+        buffer,
+    })(i)
 }
-pub fn parse_demo(i: &[u8], parse_mode: MsgDataParseMode) -> Result<Demo> {}
@@ -0,0 +1,1196 @@
+//! 네트워크 메세지(svc_*) 파싱 모듈
+
+use std::ops::Range;
+
+use nom::{
+    bytes::complete::take,
+    combinator::{map, peek},
+    multi::count,
+    number::complete::{le_f32, le_i8, le_i16, le_i32, le_u8, le_u16, le_u32},
+    sequence::tuple,
+};
+
+use crate::{
+    bitstream::BitReader,
+    delta::{decoder_field_from_delta, delta_description_decoder, parse_delta},
+    nom_helper::{Result, nom_fail, null_string, take_bytes, take_point_coord, take_point_float},
+    protocol::ProtocolLayout,
+    types::*,
+};
+
+pub const SVC_BAD: u8 = 0;
+pub const SVC_NOP: u8 = 1;
+pub const SVC_DISCONNECT: u8 = 2;
+pub const SVC_EVENT: u8 = 3;
+pub const SVC_VERSION: u8 = 4;
+pub const SVC_SETVIEW: u8 = 5;
+pub const SVC_SOUND: u8 = 6;
+pub const SVC_TIME: u8 = 7;
+pub const SVC_PRINT: u8 = 8;
+pub const SVC_STUFFTEXT: u8 = 9;
+pub const SVC_SETANGLE: u8 = 10;
+pub const SVC_SERVERINFO: u8 = 11;
+pub const SVC_LIGHTSTYLE: u8 = 12;
+pub const SVC_UPDATEUSERINFO: u8 = 13;
+pub const SVC_DELTADESCRIPTION: u8 = 14;
+pub const SVC_CLIENTDATA: u8 = 15;
+pub const SVC_STOPSOUND: u8 = 16;
+pub const SVC_PINGS: u8 = 17;
+pub const SVC_PARTICLE: u8 = 18;
+pub const SVC_DAMAGE: u8 = 19;
+pub const SVC_SPAWNSTATIC: u8 = 20;
+pub const SVC_EVENT_RELIABLE: u8 = 21;
+pub const SVC_SPAWNBASELINE: u8 = 22;
+pub const SVC_TEMPENTITY: u8 = 23;
+pub const SVC_SETPAUSE: u8 = 24;
+pub const SVC_SIGNONNUM: u8 = 25;
+pub const SVC_CENTERPRINT: u8 = 26;
+pub const SVC_KILLEDMONSTER: u8 = 27;
+pub const SVC_FOUNDSECRET: u8 = 28;
+pub const SVC_SPAWNSTATICSOUND: u8 = 29;
+pub const SVC_INTERMISSION: u8 = 30;
+pub const SVC_FINALE: u8 = 31;
+pub const SVC_CDTRACK: u8 = 32;
+pub const SVC_RESTORE: u8 = 33;
+pub const SVC_CUTSCENE: u8 = 34;
+pub const SVC_WEAPONANIM: u8 = 35;
+pub const SVC_DECALNAME: u8 = 36;
+pub const SVC_ROOMTYPE: u8 = 37;
+pub const SVC_ADDANGLE: u8 = 38;
+pub const SVC_NEWUSERMSG: u8 = 39;
+pub const SVC_PACKETENTITIES: u8 = 40;
+pub const SVC_DELTAPACKETENTITIES: u8 = 41;
+pub const SVC_CHOKE: u8 = 42;
+pub const SVC_RESOURCELIST: u8 = 43;
+pub const SVC_NEWMOVEVARS: u8 = 44;
+pub const SVC_RESOURCEREQUEST: u8 = 45;
+pub const SVC_CUSTOMIZATION: u8 = 46;
+pub const SVC_CROSSHAIRANGLE: u8 = 47;
+pub const SVC_SOUNDFADE: u8 = 48;
+pub const SVC_FILETXFERFAILED: u8 = 49;
+pub const SVC_HLTV: u8 = 50;
+pub const SVC_DIRECTOR: u8 = 51;
+pub const SVC_VOICEINIT: u8 = 52;
+pub const SVC_VOICEDATA: u8 = 53;
+pub const SVC_SENDEXTRAINFO: u8 = 54;
+pub const SVC_TIMESCALE: u8 = 55;
+pub const SVC_RESOURCELOCATION: u8 = 56;
+pub const SVC_SENDCVARVALUE: u8 = 57;
+pub const SVC_SENDCVARVALUE2: u8 = 58;
+
+/// 이 번호부터는 svc_newusermsg 로 등록된 사용자 메세지
+pub const USER_MESSAGE_START: u8 = 64;
+
+/// 프레임 하나의 네트워크 메세지 페이로드 전체를 파싱한다.
+///
+/// 해석할 수 없는 메세지를 만나면 그 위치부터 끝까지를 `NetMessage::Unparsed` 로 남긴다.
+/// 메세지 길이를 알 수 없으면 뒤따르는 메세지의 시작 위치도 알 수 없기 때문이다.
+pub fn parse_netmsg<'a>(i: &'a [u8], aux: &AuxRefCell) -> Result<'a, Vec<NetMessage>> {
+    let messages = parse_netmsg_spans(i, aux)
+        .into_iter()
+        .map(|(_, message)| message)
+        .collect();
+
+    Ok((&i[i.len()..], messages))
+}
+
+/// `parse_netmsg` 와 같지만 메세지마다 페이로드 안의 바이트 범위를 함께 돌려준다.
+/// 원본 페이로드에서 일부 메세지만 바꿔 쓸 때 쓴다.
+pub fn parse_netmsg_spans(i: &[u8], aux: &AuxRefCell) -> Vec<(Range<usize>, NetMessage)> {
+    let mut input = i;
+    let mut messages = vec![];
+
+    while !input.is_empty() {
+        let start = i.len() - input.len();
+
+        match parse_single_netmsg(input, aux) {
+            Ok((rest, message)) => {
+                messages.push((start..i.len() - rest.len(), message));
+                input = rest;
+            }
+            Err(_) => {
+                messages.push((start..i.len(), NetMessage::Unparsed(input.to_vec())));
+                input = &input[input.len()..];
+            }
+        }
+    }
+
+    messages
+}
+
+fn parse_single_netmsg<'a>(i: &'a [u8], aux: &AuxRefCell) -> Result<'a, NetMessage> {
+    let (_, message_type) = peek(le_u8)(i)?;
+
+    if message_type >= USER_MESSAGE_START {
+        return map(|i| parse_user_message(i, aux), NetMessage::UserMessage)(i);
+    }
+
+    let (i, message_type) = le_u8(i)?;
+    let (i, message) = match message_type {
+        SVC_BAD => return nom_fail(i),
+        SVC_NOP => (i, EngineMessage::SvcNop),
+        SVC_DISCONNECT => map(null_string, |reason| {
+            EngineMessage::SvcDisconnect(SvcDisconnect { reason })
+        })(i)?,
+        SVC_EVENT => parse_event(i, aux)?,
+        SVC_VERSION => map(le_u32, |protocol_version| {
+            EngineMessage::SvcVersion(SvcVersion { protocol_version })
+        })(i)?,
+        SVC_SETVIEW => map(le_i16, |entity_index| {
+            EngineMessage::SvcSetView(SvcSetView { entity_index })
+        })(i)?,
+        SVC_SOUND => parse_sound(i)?,
+        SVC_TIME => map(le_f32, |time| EngineMessage::SvcTime(SvcTime { time }))(i)?,
+        SVC_PRINT => map(null_string, |message| {
+            EngineMessage::SvcPrint(SvcPrint { message })
+        })(i)?,
+        SVC_STUFFTEXT => map(null_string, |command| {
+            EngineMessage::SvcStuffText(SvcStuffText { command })
+        })(i)?,
+        SVC_SETANGLE => map(tuple((le_i16, le_i16, le_i16)), |(pitch, yaw, roll)| {
+            EngineMessage::SvcSetAngle(SvcSetAngle { pitch, yaw, roll })
+        })(i)?,
+        SVC_SERVERINFO => parse_server_info(i, aux)?,
+        SVC_LIGHTSTYLE => map(tuple((le_u8, null_string)), |(index, light_info)| {
+            EngineMessage::SvcLightStyle(SvcLightStyle { index, light_info })
+        })(i)?,
+        SVC_UPDATEUSERINFO => map(
+            tuple((le_u8, le_u32, null_string, take_bytes(16))),
+            |(index, id, user_info, cd_key_hash)| {
+                EngineMessage::SvcUpdateUserInfo(SvcUpdateUserInfo {
+                    index,
+                    id,
+                    user_info,
+                    cd_key_hash,
+                })
+            },
+        )(i)?,
+        SVC_DELTADESCRIPTION => parse_delta_description(i, aux)?,
+        SVC_CLIENTDATA => parse_client_data(i, aux)?,
+        SVC_STOPSOUND => map(le_i16, |entity_index| {
+            EngineMessage::SvcStopSound(SvcStopSound { entity_index })
+        })(i)?,
+        SVC_PINGS => parse_pings(i)?,
+        SVC_PARTICLE => map(
+            tuple((take_point_coord, count(le_i8, 3), le_u8, le_u8)),
+            |(origin, direction, count, color)| {
+                EngineMessage::SvcParticle(SvcParticle {
+                    origin,
+                    direction: [direction[0], direction[1], direction[2]],
+                    count,
+                    color,
+                })
+            },
+        )(i)?,
+        SVC_SPAWNSTATIC => parse_spawn_static(i)?,
+        SVC_EVENT_RELIABLE => parse_event_reliable(i, aux)?,
+        SVC_SPAWNBASELINE => parse_spawn_baseline(i, aux)?,
+        SVC_TEMPENTITY => parse_temp_entity(i)?,
+        SVC_SETPAUSE => map(le_u8, |is_paused| {
+            EngineMessage::SvcSetPause(SvcSetPause { is_paused })
+        })(i)?,
+        SVC_SIGNONNUM => map(le_u8, |sign| EngineMessage::SvcSignOnNum(SvcSignOnNum { sign }))(i)?,
+        SVC_CENTERPRINT => map(null_string, |message| {
+            EngineMessage::SvcCenterPrint(SvcCenterPrint { message })
+        })(i)?,
+        SVC_KILLEDMONSTER => (i, EngineMessage::SvcKilledMonster),
+        SVC_FOUNDSECRET => (i, EngineMessage::SvcFoundSecret),
+        SVC_SPAWNSTATICSOUND => map(
+            tuple((take_point_coord, le_u16, le_u8, le_u8, le_u16, le_u8, le_u8)),
+            |(origin, sound_index, volume, attenuation, entity_index, pitch, flags)| {
+                EngineMessage::SvcSpawnStaticSound(SvcSpawnStaticSound {
+                    origin,
+                    sound_index,
+                    volume,
+                    attenuation,
+                    entity_index,
+                    pitch,
+                    flags,
+                })
+            },
+        )(i)?,
+        SVC_INTERMISSION => (i, EngineMessage::SvcIntermission),
+        SVC_FINALE => map(null_string, |text| EngineMessage::SvcFinale(SvcFinale { text }))(i)?,
+        SVC_CDTRACK => map(tuple((le_u8, le_u8)), |(track, loop_track)| {
+            EngineMessage::SvcCdTrack(SvcCdTrack { track, loop_track })
+        })(i)?,
+        SVC_RESTORE => {
+            let (i, (save_name, map_count)) = tuple((null_string, le_u8))(i)?;
+            let (i, maps) = count(null_string, map_count as usize)(i)?;
+            (i, EngineMessage::SvcRestore(SvcRestore { save_name, maps }))
+        }
+        SVC_CUTSCENE => map(null_string, |text| {
+            EngineMessage::SvcCutscene(SvcCutscene { text })
+        })(i)?,
+        SVC_WEAPONANIM => map(
+            tuple((le_u8, le_u8)),
+            |(sequence_number, weapon_model_body_group)| {
+                EngineMessage::SvcWeaponAnim(SvcWeaponAnim {
+                    sequence_number,
+                    weapon_model_body_group,
+                })
+            },
+        )(i)?,
+        SVC_DECALNAME => map(tuple((le_u8, null_string)), |(position_index, decal_name)| {
+            EngineMessage::SvcDecalName(SvcDecalName {
+                position_index,
+                decal_name,
+            })
+        })(i)?,
+        SVC_ROOMTYPE => map(le_u16, |room_type| {
+            EngineMessage::SvcRoomType(SvcRoomType { room_type })
+        })(i)?,
+        SVC_ADDANGLE => map(le_i16, |angle_to_add| {
+            EngineMessage::SvcAddAngle(SvcAddAngle { angle_to_add })
+        })(i)?,
+        SVC_NEWUSERMSG => parse_new_user_msg(i, aux)?,
+        SVC_PACKETENTITIES => parse_packet_entities(i, aux)?,
+        SVC_DELTAPACKETENTITIES => parse_delta_packet_entities(i, aux)?,
+        SVC_CHOKE => (i, EngineMessage::SvcChoke),
+        SVC_RESOURCELIST => parse_resource_list(i)?,
+        SVC_NEWMOVEVARS => parse_new_movevars(i)?,
+        SVC_RESOURCEREQUEST => map(tuple((le_i32, le_i32)), |(spawn_count, unknown)| {
+            EngineMessage::SvcResourceRequest(SvcResourceRequest {
+                spawn_count,
+                unknown,
+            })
+        })(i)?,
+        SVC_CUSTOMIZATION => parse_customization(i)?,
+        SVC_CROSSHAIRANGLE => map(tuple((le_i8, le_i8)), |(pitch, yaw)| {
+            EngineMessage::SvcCrosshairAngle(SvcCrosshairAngle { pitch, yaw })
+        })(i)?,
+        SVC_SOUNDFADE => map(
+            tuple((le_u8, le_u8, le_u8, le_u8)),
+            |(initial_percent, hold_time, fade_out_time, fade_in_time)| {
+                EngineMessage::SvcSoundFade(SvcSoundFade {
+                    initial_percent,
+                    hold_time,
+                    fade_out_time,
+                    fade_in_time,
+                })
+            },
+        )(i)?,
+        SVC_FILETXFERFAILED => map(null_string, |file_name| {
+            EngineMessage::SvcFileTxferFailed(SvcFileTxferFailed { file_name })
+        })(i)?,
+        SVC_HLTV => parse_hltv(i)?,
+        SVC_DIRECTOR => {
+            let (i, length) = le_u8(i)?;
+            let (_, command) = peek(le_u8)(i)?;
+            let (i, message) = take_bytes(length as usize)(i)?;
+            (
+                i,
+                EngineMessage::SvcDirector(SvcDirector {
+                    length,
+                    command,
+                    message,
+                }),
+            )
+        }
+        SVC_VOICEINIT => map(tuple((null_string, le_i8)), |(codec_name, quality)| {
+            EngineMessage::SvcVoiceInit(SvcVoiceInit {
+                codec_name,
+                quality,
+            })
+        })(i)?,
+        SVC_VOICEDATA => {
+            let (i, (player_index, size)) = tuple((le_u8, le_u16))(i)?;
+            let (i, data) = take_bytes(size as usize)(i)?;
+            (
+                i,
+                EngineMessage::SvcVoiceData(SvcVoiceData {
+                    player_index,
+                    size,
+                    data,
+                }),
+            )
+        }
+        SVC_SENDEXTRAINFO => map(tuple((null_string, le_u8)), |(fallback_dir, can_cheat)| {
+            EngineMessage::SvcSendExtraInfo(SvcSendExtraInfo {
+                fallback_dir,
+                can_cheat,
+            })
+        })(i)?,
+        SVC_TIMESCALE => map(le_f32, |time_scale| {
+            EngineMessage::SvcTimeScale(SvcTimeScale { time_scale })
+        })(i)?,
+        SVC_RESOURCELOCATION => map(null_string, |sv_downloadurl| {
+            EngineMessage::SvcResourceLocation(SvcResourceLocation { sv_downloadurl })
+        })(i)?,
+        SVC_SENDCVARVALUE => map(null_string, |name| {
+            EngineMessage::SvcSendCvarValue(SvcSendCvarValue { name })
+        })(i)?,
+        SVC_SENDCVARVALUE2 => map(tuple((le_u32, null_string)), |(request_id, name)| {
+            EngineMessage::SvcSendCvarValue2(SvcSendCvarValue2 { request_id, name })
+        })(i)?,
+        // 미사용 메세지
+        _ => return nom_fail(i),
+    };
+
+    Ok((i, NetMessage::EngineMessage(Box::new(message))))
+}
+
+fn parse_user_message<'a>(i: &'a [u8], aux: &AuxRefCell) -> Result<'a, UserMessage> {
+    let (i, id) = le_u8(i)?;
+
+    let aux = aux.borrow();
+    let Some(registered) = aux.custom_messages.get(&id) else {
+        return nom_fail(i);
+    };
+
+    let (i, data) = if registered.size == u8::MAX {
+        let (i, length) = le_u8(i)?;
+        take_bytes(length as usize)(i)?
+    } else {
+        take_bytes(registered.size as usize)(i)?
+    };
+
+    Ok((
+        i,
+        UserMessage {
+            id,
+            name: registered.name.clone(),
+            data,
+        },
+    ))
+}
+
+fn parse_server_info<'a>(i: &'a [u8], aux: &AuxRefCell) -> Result<'a, EngineMessage> {
+    let (
+        i,
+        (
+            protocol,
+            spawn_count,
+            map_checksum,
+            client_dll_hash,
+            max_players,
+            player_index,
+            is_deathmatch,
+            game_dir,
+            hostname,
+            map_file_name,
+            map_cycle,
+        ),
+    ) = tuple((
+        le_i32,
+        le_i32,
+        le_i32,
+        take_bytes(16),
+        le_u8,
+        le_u8,
+        le_u8,
+        null_string,
+        null_string,
+        null_string,
+        null_string,
+    ))(i)?;
+
+    // 메세지 자체의 프로토콜로 판단한다. 모르는 버전이면 현재 Steam 과 같다고 본다.
+    let layout = ProtocolLayout::new(protocol).unwrap_or_default();
+    let (i, unknown) = if layout.server_info_trailing_byte {
+        map(le_u8, Some)(i)?
+    } else {
+        (i, None)
+    };
+
+    aux.borrow_mut().max_client = max_players;
+
+    Ok((
+        i,
+        EngineMessage::SvcServerInfo(SvcServerInfo {
+            protocol,
+            spawn_count,
+            map_checksum,
+            client_dll_hash,
+            max_players,
+            player_index,
+            is_deathmatch,
+            game_dir,
+            hostname,
+            map_file_name,
+            map_cycle,
+            unknown,
+        }),
+    ))
+}
+
+fn parse_new_user_msg<'a>(i: &'a [u8], aux: &AuxRefCell) -> Result<'a, EngineMessage> {
+    let (i, (index, size, name)) = tuple((le_u8, le_u8, take_bytes(16)))(i)?;
+
+    let message = SvcNewUserMsg { index, size, name };
+    aux.borrow_mut()
+        .custom_messages
+        .insert(index, message.clone());
+
+    Ok((i, EngineMessage::SvcNewUserMsg(message)))
+}
+
+fn parse_spawn_static(i: &[u8]) -> Result<'_, EngineMessage> {
+    let (
+        i,
+        (
+            model_index,
+            sequence,
+            frame,
+            color_map,
+            skin,
+            origin_x,
+            rotation_x,
+            origin_y,
+            rotation_y,
+            origin_z,
+            rotation_z,
+            render_mode,
+        ),
+    ) = tuple((
+        le_i16, le_u8, le_u8, le_u16, le_u8, le_i16, le_i8, le_i16, le_i8, le_i16, le_i8, le_u8,
+    ))(i)?;
+
+    let (i, render_amt, render_color, render_fx) = if render_mode != 0 {
+        let (i, (render_amt, r, g, b, render_fx)) =
+            tuple((le_u8, le_u8, le_u8, le_u8, le_u8))(i)?;
+        (i, Some(render_amt), Some([r, g, b]), Some(render_fx))
+    } else {
+        (i, None, None, None)
+    };
+
+    Ok((
+        i,
+        EngineMessage::SvcSpawnStatic(SvcSpawnStatic {
+            model_index,
+            sequence,
+            frame,
+            color_map,
+            skin,
+            origin_x,
+            rotation_x,
+            origin_y,
+            rotation_y,
+            origin_z,
+            rotation_z,
+            render_mode,
+            render_amt,
+            render_color,
+            render_fx,
+        }),
+    ))
+}
+
+/// TE_* 종류별 고정 데이터 길이. 가변 길이인 종류는 `None`.
+fn temp_entity_size(entity_type: u8) -> Option<usize> {
+    let size = match entity_type {
+        0 => 24,
+        1 => 20,
+        2 => 6,
+        3 => 11,
+        4 => 6,
+        5 => 10,
+        6 => 12,
+        7 => 17,
+        8 => 16,
+        9 => 6,
+        10 => 6,
+        11 => 6,
+        12 => 8,
+        14 => 9,
+        15 => 19,
+        17 => 10,
+        18 => 16,
+        19..=21 => 24,
+        22 => 10,
+        23 => 11,
+        24 => 16,
+        25 => 19,
+        27 => 12,
+        28 => 16,
+        30 => 17,
+        31 => 17,
+        99 => 2,
+        100 => 10,
+        101 => 14,
+        102 => 12,
+        103 => 14,
+        104 => 9,
+        105 => 5,
+        106 => 17,
+        107 => 13,
+        108 => 24,
+        109 => 9,
+        110 => 17,
+        111 => 7,
+        112 => 10,
+        113 => 19,
+        114 => 19,
+        115 => 12,
+        116 => 7,
+        117 => 7,
+        118 => 9,
+        119 => 16,
+        120 => 18,
+        121 => 5,
+        122 => 10,
+        123 => 9,
+        124 => 7,
+        125 => 1,
+        126 => 18,
+        127 => 15,
+        _ => return None,
+    };
+
+    Some(size)
+}
+
+const TE_BSPDECAL: u8 = 13;
+pub(crate) const TE_TEXTMESSAGE: u8 = 29;
+
+fn parse_temp_entity(i: &[u8]) -> Result<'_, EngineMessage> {
+    let (i, entity_type) = le_u8(i)?;
+    let start = i;
+
+    let (i, _) = match entity_type {
+        TE_BSPDECAL => {
+            let (i, (_, _, entity_index)) = tuple((take_point_coord, le_i16, le_i16))(i)?;
+            if entity_index != 0 {
+                take(2usize)(i)?
+            } else {
+                (i, &i[..0])
+            }
+        }
+        TE_TEXTMESSAGE => {
+            let (i, (_, _, _, effect)) = tuple((le_u8, le_i16, le_i16, le_u8))(i)?;
+            // 색상 8바이트, fade in/out, hold time
+            let (i, _) = take(14usize)(i)?;
+            let (i, _) = if effect == 2 {
+                take(2usize)(i)?
+            } else {
+                (i, &i[..0])
+            };
+            let (i, _) = null_string(i)?;
+            (i, &i[..0])
+        }
+        _ => match temp_entity_size(entity_type) {
+            Some(size) => take(size)(i)?,
+            None => return nom_fail(i),
+        },
+    };
+
+    let entity = start[..start.len() - i.len()].to_vec();
+
+    Ok((
+        i,
+        EngineMessage::SvcTempEntity(SvcTempEntity {
+            entity_type,
+            entity,
+        }),
+    ))
+}
+
+fn parse_new_movevars(i: &[u8]) -> Result<'_, EngineMessage> {
+    let (
+        i,
+        (
+            (
+                gravity,
+                stop_speed,
+                max_speed,
+                spectator_max_speed,
+                accelerate,
+                airaccelerate,
+                water_accelerate,
+                friction,
+            ),
+            (
+                edge_friction,
+                water_friction,
+                ent_gravity,
+                bounce,
+                step_size,
+                max_velocity,
+                z_max,
+                wave_height,
+            ),
+            footsteps,
+            roll_angle,
+            roll_speed,
+            sky_color,
+            sky_vec,
+            sky_name,
+        ),
+    ) = tuple((
+        tuple((le_f32, le_f32, le_f32, le_f32, le_f32, le_f32, le_f32, le_f32)),
+        tuple((le_f32, le_f32, le_f32, le_f32, le_f32, le_f32, le_f32, le_f32)),
+        le_u8,
+        le_f32,
+        le_f32,
+        take_point_float,
+        take_point_float,
+        null_string,
+    ))(i)?;
+
+    Ok((
+        i,
+        EngineMessage::SvcNewMoveVars(SvcNewMoveVars {
+            gravity,
+            stop_speed,
+            max_speed,
+            spectator_max_speed,
+            accelerate,
+            airaccelerate,
+            water_accelerate,
+            friction,
+            edge_friction,
+            water_friction,
+            ent_gravity,
+            bounce,
+            step_size,
+            max_velocity,
+            z_max,
+            wave_height,
+            footsteps,
+            roll_angle,
+            roll_speed,
+            sky_color,
+            sky_vec,
+            sky_name,
+        }),
+    ))
+}
+
+/// RES_CUSTOM 플래그가 있으면 md5 해시가 따라온다.
+const RES_CUSTOM: u8 = 1 << 2;
+
+fn parse_customization(i: &[u8]) -> Result<'_, EngineMessage> {
+    let (i, (player_index, type_, name, index, download_size, flags)) =
+        tuple((le_u8, le_u8, null_string, le_u16, le_u32, le_u8))(i)?;
+
+    let (i, md5_hash) = if flags & RES_CUSTOM != 0 {
+        map(take_bytes(16), Some)(i)?
+    } else {
+        (i, None)
+    };
+
+    Ok((
+        i,
+        EngineMessage::SvcCustomization(SvcCustomization {
+            player_index,
+            type_,
+            name,
+            index,
+            download_size,
+            flags,
+            md5_hash,
+        }),
+    ))
+}
+
+const HLTV_ACTIVE: u8 = 0;
+const HLTV_STATUS: u8 = 1;
+const HLTV_LISTEN: u8 = 2;
+
+fn parse_hltv(i: &[u8]) -> Result<'_, EngineMessage> {
+    let (i, mode) = le_u8(i)?;
+
+    let (i, hltv) = match mode {
+        HLTV_ACTIVE => (i, SvcHltv::Active),
+        // long, short, word, long, long, word
+        HLTV_STATUS => map(take_bytes(18), SvcHltv::Status)(i)?,
+        HLTV_LISTEN => map(null_string, SvcHltv::Listen)(i)?,
+        _ => return nom_fail(i),
+    };
+
+    Ok((i, EngineMessage::SvcHltv(hltv)))
+}
+
+/// 비트 단위로 기록된 메세지를 읽는다. MSG_EndBitReading 처럼 마지막 바이트는 통째로 소비한다.
+fn parse_bits<'a, T>(
+    i: &'a [u8],
+    f: impl FnOnce(&mut BitReader) -> Option<T>,
+) -> Result<'a, T> {
+    let mut br = BitReader::new(i);
+
+    let Some(value) = f(&mut br) else {
+        return nom_fail(i);
+    };
+
+    Ok((&i[br.consumed_bytes()..], value))
+}
+
+/// 등록되지 않은 디코더로 델타를 읽으려 하면 실패한다.
+fn parse_named_delta(br: &mut BitReader, aux: &Aux, name: &str) -> Option<Delta> {
+    parse_delta(br, aux.delta_decoders.get(name)?)
+}
+
+fn parse_event<'a>(i: &'a [u8], aux: &AuxRefCell) -> Result<'a, EngineMessage> {
+    let aux = aux.borrow();
+
+    map(
+        |i| {
+            parse_bits(i, |br| {
+                let event_count = br.read_n(5)?;
+                let mut events = vec![];
+
+                for _ in 0..event_count {
+                    let event_index = br.read_n(10)? as u16;
+                    let mut event = EventS {
+                        event_index,
+                        packet_index: None,
+                        has_delta: None,
+                        delta: None,
+                        fire_time: None,
+                    };
+
+                    if br.read_bit()? {
+                        event.packet_index = Some(br.read_n(11)? as u16);
+
+                        let has_delta = br.read_bit()?;
+                        event.has_delta = Some(has_delta);
+                        if has_delta {
+                            event.delta = Some(parse_named_delta(br, &aux, "event_t")?);
+                        }
+                    }
+
+                    if br.read_bit()? {
+                        event.fire_time = Some(br.read_n(16)? as u16);
+                    }
+
+                    events.push(event);
+                }
+
+                Some(events)
+            })
+        },
+        |events| EngineMessage::SvcEvent(SvcEvent { events }),
+    )(i)
+}
+
+const SND_VOLUME: u16 = 1 << 0;
+const SND_ATTENUATION: u16 = 1 << 1;
+const SND_LARGE_INDEX: u16 = 1 << 2;
+const SND_PITCH: u16 = 1 << 3;
+
+fn parse_sound(i: &[u8]) -> Result<'_, EngineMessage> {
+    map(
+        |i| {
+            parse_bits(i, |br| {
+                let flags = br.read_n(9)? as u16;
+                let volume = if flags & SND_VOLUME != 0 {
+                    Some(br.read_n(8)? as u8)
+                } else {
+                    None
+                };
+                let attenuation = if flags & SND_ATTENUATION != 0 {
+                    Some(br.read_n(8)? as u8)
+                } else {
+                    None
+                };
+                let channel = br.read_n(3)? as u8;
+                let entity_index = br.read_n(11)? as u16;
+                let sound_index = if flags & SND_LARGE_INDEX != 0 {
+                    br.read_n(16)?
+                } else {
+                    br.read_n(8)?
+                } as u16;
+                let origin = br.read_vec3_coord()?;
+                let pitch = if flags & SND_PITCH != 0 {
+                    Some(br.read_n(8)? as u8)
+                } else {
+                    None
+                };
+
+                Some(SvcSound {
+                    flags,
+                    volume,
+                    attenuation,
+                    channel,
+                    entity_index,
+                    sound_index,
+                    origin,
+                    pitch,
+                })
+            })
+        },
+        EngineMessage::SvcSound,
+    )(i)
+}
+
+fn parse_delta_description<'a>(i: &'a [u8], aux: &AuxRefCell) -> Result<'a, EngineMessage> {
+    let (i, (name, total_fields)) = tuple((null_string, le_u16))(i)?;
+
+    let meta = delta_description_decoder();
+    let (i, fields) = parse_bits(i, |br| {
+        (0..total_fields)
+            .map(|_| parse_delta(br, &meta).map(|delta| decoder_field_from_delta(&delta)))
+            .collect::<Option<Vec<_>>>()
+    })?;
+
+    aux.borrow_mut()
+        .delta_decoders
+        .insert(bytes_to_string(&name), fields.clone());
+
+    Ok((
+        i,
+        EngineMessage::SvcDeltaDescription(SvcDeltaDescription {
+            name,
+            total_fields,
+            fields,
+        }),
+    ))
+}
+
+fn parse_client_data<'a>(i: &'a [u8], aux: &AuxRefCell) -> Result<'a, EngineMessage> {
+    let aux = aux.borrow();
+
+    // HLTV 는 시점 플레이어가 없으므로 메세지 id 만 온다
+    if aux.is_hltv {
+        return Ok((
+            i,
+            EngineMessage::SvcClientData(SvcClientData {
+                delta_update_mask: None,
+                client_data: Delta::new(),
+                weapon_data: vec![],
+            }),
+        ));
+    }
+
+    map(
+        |i| {
+            parse_bits(i, |br| {
+                let delta_update_mask = if br.read_bit()? {
+                    Some(br.read_n(8)? as u8)
+                } else {
+                    None
+                };
+
+                let client_data = parse_named_delta(br, &aux, "clientdata_t")?;
+
+                let mut weapon_data = vec![];
+                while br.read_bit()? {
+                    weapon_data.push(ClientDataWeaponData {
+                        weapon_index: br.read_n(6)? as u8,
+                        weapon_data: parse_named_delta(br, &aux, "weapon_data_t")?,
+                    });
+                }
+
+                Some(SvcClientData {
+                    delta_update_mask,
+                    client_data,
+                    weapon_data,
+                })
+            })
+        },
+        EngineMessage::SvcClientData,
+    )(i)
+}
+
+fn parse_pings(i: &[u8]) -> Result<'_, EngineMessage> {
+    map(
+        |i| {
+            parse_bits(i, |br| {
+                let mut pings = vec![];
+
+                while br.read_bit()? {
+                    pings.push(PingS {
+                        player_id: br.read_n(5)? as u8,
+                        ping: br.read_n(12)? as u16,
+                        loss: br.read_n(7)? as u8,
+                    });
+                }
+
+                Some(pings)
+            })
+        },
+        |pings| EngineMessage::SvcPings(SvcPings { pings }),
+    )(i)
+}
+
+fn parse_event_reliable<'a>(i: &'a [u8], aux: &AuxRefCell) -> Result<'a, EngineMessage> {
+    let aux = aux.borrow();
+
+    map(
+        |i| {
+            parse_bits(i, |br| {
+                let event_index = br.read_n(10)? as u16;
+                let event_args = parse_named_delta(br, &aux, "event_t")?;
+                let fire_time = if br.read_bit()? {
+                    Some(br.read_n(16)? as u16)
+                } else {
+                    None
+                };
+
+                Some(SvcEventReliable {
+                    event_index,
+                    event_args,
+                    fire_time,
+                })
+            })
+        },
+        EngineMessage::SvcEventReliable,
+    )(i)
+}
+
+pub(crate) const ENTITY_NORMAL: u8 = 1 << 0;
+const ENTITY_INDEX_END: u32 = (1 << 11) - 1;
+
+/// 1..=max_client 번 엔티티는 플레이어다.
+fn is_player_entity(aux: &Aux, entity_index: u16) -> bool {
+    entity_index > 0 && entity_index <= aux.max_client as u16
+}
+
+pub(crate) fn entity_decoder_name(aux: &Aux, entity_index: u16, custom: bool) -> &'static str {
+    if custom {
+        "custom_entity_state_t"
+    } else if is_player_entity(aux, entity_index) {
+        "entity_state_player_t"
+    } else {
+        "entity_state_t"
+    }
+}
+
+fn parse_spawn_baseline<'a>(i: &'a [u8], aux: &AuxRefCell) -> Result<'a, EngineMessage> {
+    let (i, message) = {
+        let aux = aux.borrow();
+
+        parse_bits(i, |br| {
+            let mut entities = vec![];
+
+            loop {
+                let index = br.read_n(11)?;
+                if index == ENTITY_INDEX_END {
+                    break;
+                }
+
+                let index = index as u16;
+                let type_ = br.read_n(2)? as u8;
+                let custom = type_ & ENTITY_NORMAL == 0;
+                let delta = parse_named_delta(br, &aux, entity_decoder_name(&aux, index, custom))?;
+
+                entities.push(BaselineEntity {
+                    index,
+                    type_,
+                    delta,
+                });
+            }
+
+            // 종료 표시는 0xFFFF 16비트다.
+            if br.read_n(5)? != (1 << 5) - 1 {
+                return None;
+            }
+
+            let extra_count = br.read_n(6)?;
+            let extra_data = (0..extra_count)
+                .map(|_| parse_named_delta(br, &aux, "entity_state_t"))
+                .collect::<Option<Vec<_>>>()?;
+
+            Some(SvcSpawnBaseline {
+                entities,
+                extra_data,
+            })
+        })?
+    };
+
+    aux.borrow_mut().instanced_baseline_count = message.extra_data.len() as u8;
+
+    Ok((i, EngineMessage::SvcSpawnBaseline(message)))
+}
+
+/// 엔티티 목록은 16비트 0 으로 끝난다.
+fn is_entity_list_end(br: &mut BitReader) -> Option<bool> {
+    if br.peek_n(16)? == 0 {
+        br.read_n(16)?;
+        return Some(true);
+    }
+
+    Some(false)
+}
+
+/// 절대 번호(11비트) 또는 이전 번호와의 차이(6비트)
+fn parse_entity_index(br: &mut BitReader, previous: u16) -> Option<(u16, EntityIndexHeader)> {
+    if br.read_bit()? {
+        let index = br.read_n(11)? as u16;
+        Some((index, EntityIndexHeader::Absolute(index)))
+    } else {
+        let difference = br.read_n(6)? as u8;
+        Some((
+            previous + difference as u16,
+            EntityIndexHeader::Difference(difference),
+        ))
+    }
+}
+
+fn parse_baseline_index(br: &mut BitReader, aux: &Aux) -> Option<Option<Option<u8>>> {
+    if aux.instanced_baseline_count == 0 {
+        return Some(None);
+    }
+
+    if br.read_bit()? {
+        Some(Some(Some(br.read_n(6)? as u8)))
+    } else {
+        Some(Some(None))
+    }
+}
+
+fn parse_packet_entities<'a>(i: &'a [u8], aux: &AuxRefCell) -> Result<'a, EngineMessage> {
+    let (i, entity_count) = le_u16(i)?;
+    let aux = aux.borrow();
+
+    map(
+        |i| {
+            parse_bits(i, |br| {
+                let mut entities = vec![];
+                let mut entity_index = 0;
+
+                while !is_entity_list_end(br)? {
+                    let index_header;
+                    if br.read_bit()? {
+                        entity_index += 1;
+                        index_header = EntityIndexHeader::Increment;
+                    } else {
+                        (entity_index, index_header) = parse_entity_index(br, entity_index)?;
+                    }
+
+                    let has_custom_delta = br.read_bit()?;
+                    let baseline_index = parse_baseline_index(br, &aux)?;
+                    let baseline_offset = if baseline_index.flatten().is_none() {
+                        if br.read_bit()? {
+                            Some(Some(br.read_n(6)? as u8))
+                        } else {
+                            Some(None)
+                        }
+                    } else {
+                        None
+                    };
+
+                    let decoder = entity_decoder_name(&aux, entity_index, has_custom_delta);
+                    let delta = parse_named_delta(br, &aux, decoder)?;
+
+                    entities.push(PacketEntity {
+                        entity_index,
+                        index_header,
+                        has_custom_delta,
+                        baseline_index,
+                        baseline_offset,
+                        delta,
+                    });
+                }
+
+                Some(entities)
+            })
+        },
+        move |entities| {
+            EngineMessage::SvcPacketEntities(SvcPacketEntities {
+                entity_count,
+                entities,
+            })
+        },
+    )(i)
+}
+
+fn parse_delta_packet_entities<'a>(i: &'a [u8], aux: &AuxRefCell) -> Result<'a, EngineMessage> {
+    let (i, (entity_count, delta_sequence)) = tuple((le_u16, le_u8))(i)?;
+    let aux = aux.borrow();
+
+    map(
+        |i| {
+            parse_bits(i, |br| {
+                let mut entities = vec![];
+                let mut entity_index = 0;
+
+                while !is_entity_list_end(br)? {
+                    let remove_entity = br.read_bit()?;
+                    let index_header;
+                    (entity_index, index_header) = parse_entity_index(br, entity_index)?;
+
+                    let mut entity = DeltaPacketEntity {
+                        entity_index,
+                        index_header,
+                        remove_entity,
+                        has_custom_delta: None,
+                        baseline_index: None,
+                        delta: None,
+                    };
+
+                    if !remove_entity {
+                        let custom = br.read_bit()?;
+                        entity.has_custom_delta = Some(custom);
+                        entity.baseline_index = parse_baseline_index(br, &aux)?;
+
+                        let decoder = entity_decoder_name(&aux, entity_index, custom);
+                        entity.delta = Some(parse_named_delta(br, &aux, decoder)?);
+                    }
+
+                    entities.push(entity);
+                }
+
+                Some(entities)
+            })
+        },
+        move |entities| {
+            EngineMessage::SvcDeltaPacketEntities(SvcDeltaPacketEntities {
+                entity_count,
+                delta_sequence,
+                entities,
+            })
+        },
+    )(i)
+}
+
+fn parse_resource_list(i: &[u8]) -> Result<'_, EngineMessage> {
+    map(
+        |i| {
+            parse_bits(i, |br| {
+                let resource_count = br.read_n(12)?;
+                let mut resources = vec![];
+
+                for _ in 0..resource_count {
+                    let type_ = br.read_n(4)? as u8;
+                    let name = br.read_string()?;
+                    let index = br.read_n(12)? as u16;
+                    let size = br.read_n(24)?;
+                    let flags = br.read_n(3)? as u8;
+                    let md5_hash = if flags & RES_CUSTOM != 0 {
+                        Some(br.read_bytes(16)?)
+                    } else {
+                        None
+                    };
+                    let extra_info = if br.read_bit()? {
+                        Some(br.read_bytes(32)?)
+                    } else {
+                        None
+                    };
+
+                    resources.push(Resource {
+                        type_,
+                        name,
+                        index,
+                        size,
+                        flags,
+                        md5_hash,
+                        extra_info,
+                    });
+                }
+
+                let consistencies = if br.read_bit()? {
+                    let mut consistencies = vec![];
+
+                    while br.read_bit()? {
+                        consistencies.push(if br.read_bit()? {
+                            Consistency::Short(br.read_n(5)? as u8)
+                        } else {
+                            Consistency::Long(br.read_n(10)? as u16)
+                        });
+                    }
+
+                    Some(consistencies)
+                } else {
+                    None
+                };
+
+                Some(SvcResourceList {
+                    resources,
+                    consistencies,
+                })
+            })
+        },
+        EngineMessage::SvcResourceList,
+    )(i)
+}
@@ -0,0 +1,144 @@
+//! 이벤트 프레임(타입 6) 타임라인
+//!
+//! 클라이언트가 예측해서 재생한 이벤트(PLAYBACK_EVENT)가 기록된다. 이벤트 번호는 svc_resourcelist 의
+//! 이벤트 스크립트(events/usp.sc 등) 번호이므로 이름을 찾아 발사, 폭발 등으로 구분한다.
+//! 이벤트 프레임도 같은 프레임 번호의 네트워크 메세지 프레임보다 먼저 기록되므로 그 프레임에 붙인다.
+
+use std::collections::HashMap;
+
+use crate::demo::{DemoEvent, ParsedDemo};
+use crate::types::{Event, Resource};
+use crate::weapon::Weapon;
+
+/// t_eventscript
+const RESOURCE_EVENT_SCRIPT: u8 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// 무기 발사. 칼은 휘두르기, 듀얼 엘리트는 양손 모두 Elite 다.
+    Shot(Weapon),
+    /// 수류탄 폭발 (events/createexplo.sc)
+    Explosion,
+    /// 연막탄 (events/createsmoke.sc)
+    Smoke,
+    /// 라운드 시작의 데칼 지우기 (events/decal_reset.sc)
+    DecalReset,
+    /// 그 밖의 이벤트나 리소스 목록에 없는 번호
+    Other,
+}
+
+impl EventKind {
+    fn classify(name: &str) -> EventKind {
+        let name = name.to_ascii_lowercase();
+        let script = name.strip_prefix("events/").unwrap_or(&name);
+        let script = script.strip_suffix(".sc").unwrap_or(script);
+
+        match script {
+            "createexplo" => EventKind::Explosion,
+            "createsmoke" => EventKind::Smoke,
+            "decal_reset" => EventKind::DecalReset,
+            "mp5n" => EventKind::Shot(Weapon::Mp5Navy),
+            "elite_left" | "elite_right" => EventKind::Shot(Weapon::Elite),
+            _ => Weapon::from_name(script).map_or(EventKind::Other, EventKind::Shot),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlaybackEvent {
+    pub time: f32,
+    pub frame: i32,
+    /// 이벤트 뒤에 오는 `ParsedDemo::frames` 인덱스. 마지막 프레임 뒤의 이벤트면 `frames.len()`
+    pub index: usize,
+    /// 이벤트 스크립트 이름. 리소스 목록에 없으면 None
+    pub name: Option<String>,
+    pub kind: EventKind,
+    /// 원본 이벤트. 발사 위치와 각도는 `event.args` 의 origin, angles 다.
+    pub event: Event,
+}
+
+impl PlaybackEvent {
+    pub fn is_shot(&self) -> bool {
+        matches!(self.kind, EventKind::Shot(_))
+    }
+
+    /// USP, M4A1 발사의 소음기 상태. 다른 이벤트는 None
+    pub fn silenced(&self) -> Option<bool> {
+        match self.kind {
+            // USP 는 bparam1 이 빈 탄창, bparam2 가 소음기다
+            EventKind::Shot(Weapon::Usp) => Some(self.event.args.bparam2 != 0),
+            EventKind::Shot(Weapon::M4a1) => Some(self.event.args.bparam1 != 0),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EventTimeline {
+    /// LOADING 세그먼트를 제외한 이벤트 (파일 순서)
+    pub events: Vec<PlaybackEvent>,
+}
+
+impl EventTimeline {
+    pub fn from_demo(demo: &ParsedDemo) -> EventTimeline {
+        let names = event_names(&demo.resources);
+
+        let events = demo
+            .playback_events()
+            .filter_map(|(index, event)| match event {
+                DemoEvent::Event(header, event) => Some((index, header, event)),
+                _ => None,
+            })
+            .map(|(index, header, event)| {
+                let name = u16::try_from(event.index)
+                    .ok()
+                    .and_then(|event_index| names.get(&event_index))
+                    .cloned();
+
+                PlaybackEvent {
+                    time: header.time,
+                    frame: header.frame,
+                    index,
+                    kind: name
+                        .as_deref()
+                        .map_or(EventKind::Other, EventKind::classify),
+                    name,
+                    event: event.clone(),
+                }
+            })
+            .collect();
+
+        EventTimeline { events }
+    }
+
+    pub fn shots(&self) -> impl Iterator<Item = &PlaybackEvent> {
+        self.events.iter().filter(|event| event.is_shot())
+    }
+
+    /// 소음기 상태가 같은 무기의 직전 발사와 달라진 발사.
+    /// 실제로 끼우거나 뺀 시점은 이 발사와 직전 발사 사이다.
+    pub fn silencer_toggles(&self) -> Vec<&PlaybackEvent> {
+        let mut last: HashMap<EventKind, bool> = HashMap::new();
+
+        self.events
+            .iter()
+            .filter(|event| {
+                let Some(silenced) = event.silenced() else {
+                    return false;
+                };
+
+                last.insert(event.kind, silenced)
+                    .is_some_and(|previous| previous != silenced)
+            })
+            .collect()
+    }
+}
+
+/// 이벤트 번호와 스크립트 이름
+fn event_names(resources: &[Resource]) -> HashMap<u16, String> {
+    resources
+        .iter()
+        .filter(|resource| resource.type_ == RESOURCE_EVENT_SCRIPT)
+        .map(|resource| (resource.index, resource.name()))
+        .collect()
+}
@@ -0,0 +1,346 @@
+//! 플레이어 추적 모듈
+//!
+//! 녹화한 플레이어(POV)는 ref_params/clientdata 로, 나머지 플레이어는 엔티티 상태로 추적한다.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::analyze::angle_vectors;
+use crate::demo::{DemoFrame, InputButtons, Vector3};
+use crate::entity::{EntityState, EntityTracker};
+use crate::types::{EngineMessage, MessageData, NetMessage, NetworkMessage, bytes_to_string};
+
+pub const FL_ONGROUND: i32 = 1 << 9;
+pub const FL_DUCKING: i32 = 1 << 14;
+
+const SOLID_NOT: i32 = 0;
+const EF_NODRAW: i32 = 128;
+
+/// 서 있을 때 / 앉았을 때 시점 높이 (VEC_VIEW, VEC_DUCK_VIEW)
+const VIEW_HEIGHT: f32 = 17.0;
+pub(crate) const DUCK_VIEW_HEIGHT: f32 = 12.0;
+
+/// 점프 속도(약 268)로는 나올 수 없는 상승 속도. 덕탭은 한 번에 18 유닛을 올라간다.
+const DUCKTAP_MIN_SPEED: f32 = 300.0;
+
+/// 한 프레임에서의 플레이어 상태
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerState {
+    pub origin: Vector3,
+    /// 시점 각도 (pitch, yaw, roll)
+    pub angles: Vector3,
+    /// POV 는 simvel, 나머지는 엔티티 갱신 간 위치 차이로 추정한 값
+    pub velocity: Vector3,
+    /// POV 가 아니면 수직 속도로 추정한 값
+    pub onground: bool,
+    pub ducking: bool,
+    pub alive: bool,
+}
+
+/// 슬롯 하나의 프레임별 상태. `states` 는 `ParsedDemo::frames` 와 인덱스가 같다.
+#[derive(Debug, Clone)]
+pub struct PlayerTrack {
+    /// 0 부터 시작하는 슬롯 번호 (엔티티 번호 - 1)
+    pub slot: u8,
+    /// 마지막으로 받은 userinfo 의 name
+    pub name: String,
+    /// 녹화한 플레이어인지 여부
+    pub is_pov: bool,
+    /// 엔티티가 없는 프레임은 None
+    pub states: Vec<Option<PlayerState>>,
+}
+
+impl PlayerTrack {
+    pub fn entity_index(&self) -> u16 {
+        self.slot as u16 + 1
+    }
+
+    /// `extract_jump_segments` 와 렌더러에 넘길 수 있도록 이 플레이어 기준의 DemoFrame 을 만든다.
+    ///
+    /// `frames` 는 같은 데모의 `ParsedDemo::frames` 다. 살아 있지 않은 프레임은 건너뛴다.
+    pub fn demo_frames(&self, frames: &[DemoFrame]) -> Vec<DemoFrame> {
+        if self.is_pov {
+            return frames
+                .iter()
+                .zip(&self.states)
+                .filter(|(_, state)| state.is_some_and(|state| state.alive))
+                .map(|(frame, _)| frame.clone())
+                .collect();
+        }
+
+        entity_demo_frames(frames, &self.states)
+    }
+}
+
+/// 엔티티 상태로 DemoFrame 을 만든다. `frames` 와 `states` 는 같은 구간이어야 한다.
+///
+/// movevars 와 프레임 시간은 `frames` 의 것을 그대로 쓰고, 위치, 각도, 입력만 바꾼다.
+/// 다른 플레이어의 입력은 알 수 없으므로 `buttons` 는 비워 두고,
+/// 이륙 시점에 추정한 JUMP / DUCK 을 `estimated_buttons` 에 넣는다.
+pub fn entity_demo_frames(frames: &[DemoFrame], states: &[Option<PlayerState>]) -> Vec<DemoFrame> {
+    let mut result: Vec<DemoFrame> = Vec::new();
+
+    for (frame, state) in frames.iter().zip(states) {
+        let Some(state) = state.filter(|state| state.alive) else {
+            continue;
+        };
+
+        let view_height = if state.ducking { DUCK_VIEW_HEIGHT } else { VIEW_HEIGHT };
+        let (forward, right, up) = angle_vectors(&state.angles);
+
+        // 이륙한 프레임이면 버튼을 추정한다 (analyze 의 감지 기준과 같은 위치).
+        // 점프는 이륙한 프레임, 덕탭은 그 직전 프레임의 usercmd 에 들어간다.
+        let mut estimated_buttons = InputButtons::empty();
+        if !state.onground && result.last().is_some_and(|last| last.onground) && state.velocity.z > 0.0 {
+            if state.velocity.z > DUCKTAP_MIN_SPEED {
+                if let Some(last) = result.last_mut() {
+                    last.estimated_buttons |= InputButtons::DUCK;
+                }
+            } else {
+                estimated_buttons = InputButtons::JUMP;
+            }
+        }
+
+        result.push(DemoFrame {
+            vieworg: Vector3 {
+                x: state.origin.x,
+                y: state.origin.y,
+                z: state.origin.z + view_height,
+            },
+            viewangle: state.angles,
+            onground: state.onground,
+            simvel: state.velocity,
+            simorg: state.origin,
+            viewheight: Vector3 { x: 0.0, y: 0.0, z: view_height },
+            command: Vec::new(),
+            forwardmove: 0.0,
+            sidemove: 0.0,
+            upmove: 0.0,
+            buttons: InputButtons::empty(),
+            estimated_buttons,
+            impulse: 0,
+            weaponselect: 0,
+            // 다른 플레이어의 무기는 알 수 없다
+            current_weapon: None,
+            forward,
+            right,
+            up,
+            ..frame.clone()
+        });
+    }
+
+    result.iter_mut().for_each(sync_raw_fields);
+
+    result
+}
+
+/// 바꾼 위치, 각도, 입력을 ref_params 와 usercmd 에도 반영한다.
+/// 체력 같은 나머지 ref_params 값은 녹화한 플레이어의 것이 남는다.
+fn sync_raw_fields(frame: &mut DemoFrame) {
+    let ref_params = &mut frame.ref_params;
+    ref_params.vieworg = frame.vieworg.into();
+    ref_params.viewangles = frame.viewangle.into();
+    ref_params.cl_viewangles = frame.viewangle.into();
+    ref_params.forward = frame.forward.into();
+    ref_params.right = frame.right.into();
+    ref_params.up = frame.up.into();
+    ref_params.onground = frame.onground as i32;
+    ref_params.simvel = frame.simvel.into();
+    ref_params.simorg = frame.simorg.into();
+    ref_params.viewheight = frame.viewheight.into();
+
+    let user_cmd = &mut frame.user_cmd;
+    user_cmd.viewangles = frame.viewangle.into();
+    user_cmd.forwardmove = frame.forwardmove;
+    user_cmd.sidemove = frame.sidemove;
+    user_cmd.upmove = frame.upmove;
+    user_cmd.buttons = frame.buttons.bits();
+    user_cmd.impulse = frame.impulse as i8;
+    user_cmd.weaponselect = frame.weaponselect as i8;
+}
+
+/// 엔티티 갱신 사이의 움직임 추정용 상태
+#[derive(Debug, Clone, Copy)]
+struct Motion {
+    origin: Vector3,
+    time: f32,
+    velocity: Vector3,
+    onground: bool,
+}
+
+/// 네트워크 메세지 프레임을 순서대로 받아 모든 슬롯의 PlayerTrack 을 만든다.
+#[derive(Debug, Default)]
+pub struct PlayerTracker {
+    tracks: BTreeMap<u8, PlayerTrack>,
+    motions: HashMap<u8, Motion>,
+    names: HashMap<u8, String>,
+    max_client: u8,
+    pov_slot: Option<u8>,
+    /// 마지막 프레임에 엔티티 패킷이 있었는지
+    has_packet: bool,
+    frame_count: usize,
+}
+
+impl PlayerTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 서버 정보와 userinfo 를 반영한다. LOADING 세그먼트 프레임도 넘겨야 한다.
+    pub fn observe(&mut self, message: &NetworkMessage) {
+        self.has_packet = false;
+
+        let MessageData::Parse(messages) = &message.messages else {
+            return;
+        };
+
+        for message in messages {
+            let NetMessage::EngineMessage(message) = message else {
+                continue;
+            };
+
+            match message.as_ref() {
+                EngineMessage::SvcServerInfo(info) => {
+                    self.max_client = info.max_players;
+                    self.pov_slot = Some(info.player_index);
+                    self.motions.clear();
+                }
+                EngineMessage::SvcUpdateUserInfo(info) => {
+                    let user_info = bytes_to_string(&info.user_info);
+                    if let Some(name) = info_value(&user_info, "name") {
+                        self.names.insert(info.index, name.to_string());
+                    }
+                }
+                EngineMessage::SvcPacketEntities(_) | EngineMessage::SvcDeltaPacketEntities(_) => {
+                    self.has_packet = true;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// 분석 대상 프레임 하나에 대해 모든 슬롯의 상태를 기록한다.
+    pub fn record(&mut self, frame: &DemoFrame, entities: &EntityTracker) {
+        // HLTV 관전 데모의 ref_params 는 카메라이므로 모든 슬롯을 엔티티로 추적한다
+        let pov_slot = self.pov_slot.filter(|_| !frame.spectator);
+
+        for slot in 0..self.max_client {
+            let state = if Some(slot) == pov_slot {
+                Some(pov_state(frame, &entities.current().client_data))
+            } else {
+                entities
+                    .entity(slot as u16 + 1)
+                    .map(|entity| self.entity_state(slot, frame.time, entity))
+            };
+
+            let frame_count = self.frame_count;
+            let track = match (self.tracks.get_mut(&slot), state) {
+                (Some(track), _) => track,
+                (None, Some(_)) => self.tracks.entry(slot).or_insert_with(|| PlayerTrack {
+                    slot,
+                    name: String::new(),
+                    is_pov: false,
+                    states: vec![None; frame_count],
+                }),
+                (None, None) => continue,
+            };
+
+            track.is_pov = Some(slot) == pov_slot;
+            if let Some(name) = self.names.get(&slot) {
+                track.name.clone_from(name);
+            }
+            track.states.push(state);
+        }
+
+        self.frame_count += 1;
+
+        // 중간에 max_client 가 줄어든 경우에도 길이를 맞춘다
+        for track in self.tracks.values_mut() {
+            track.states.resize(self.frame_count, None);
+        }
+    }
+
+    pub fn finish(self) -> Vec<PlayerTrack> {
+        self.tracks.into_values().collect()
+    }
+
+    fn entity_state(&mut self, slot: u8, time: f32, entity: &EntityState) -> PlayerState {
+        let origin = Vector3::from(entity.origin());
+
+        let motion = match self.motions.get(&slot).copied() {
+            Some(last) if self.has_packet || !same_position(last.origin, origin) => {
+                let dt = time - last.time;
+                if dt > 0.0 {
+                    let velocity = Vector3 {
+                        x: (origin.x - last.origin.x) / dt,
+                        y: (origin.y - last.origin.y) / dt,
+                        z: (origin.z - last.origin.z) / dt,
+                    };
+                    // 수직 속도가 0 이면 바닥으로 본다. 직전 갱신에서 올라가던 중이었다면 정점이다.
+                    let onground = velocity.z == 0.0 && last.velocity.z <= 0.0;
+
+                    Motion { origin, time, velocity, onground }
+                } else {
+                    last
+                }
+            }
+            Some(last) => last,
+            None => Motion {
+                origin,
+                time,
+                velocity: Vector3 { x: 0.0, y: 0.0, z: 0.0 },
+                onground: true,
+            },
+        };
+        self.motions.insert(slot, motion);
+
+        PlayerState {
+            origin,
+            angles: view_angles(entity.angles()),
+            velocity: motion.velocity,
+            onground: motion.onground,
+            ducking: entity.integer("usehull") == 1,
+            alive: entity.integer("solid") != SOLID_NOT && entity.integer("effects") & EF_NODRAW == 0,
+        }
+    }
+}
+
+fn pov_state(frame: &DemoFrame, client_data: &EntityState) -> PlayerState {
+    let flags = client_data.integer("flags");
+
+    PlayerState {
+        origin: frame.simorg,
+        angles: frame.viewangle,
+        velocity: frame.simvel,
+        onground: frame.onground,
+        ducking: flags & FL_DUCKING != 0,
+        alive: client_data.integer("health") > 0 && client_data.integer("deadflag") == 0,
+    }
+}
+
+fn same_position(a: Vector3, b: Vector3) -> bool {
+    a.x == b.x && a.y == b.y && a.z == b.z
+}
+
+/// 엔티티의 pitch 는 시점 pitch 의 -1/3 로 전송된다.
+fn view_angles(angles: [f32; 3]) -> Vector3 {
+    let pitch = if angles[0] > 180.0 { angles[0] - 360.0 } else { angles[0] };
+
+    Vector3 {
+        x: -pitch * 3.0,
+        y: angles[1],
+        z: angles[2],
+    }
+}
+
+/// `\key\value\key\value` 형식의 info 문자열에서 값을 찾는다.
+pub fn info_value<'a>(info: &'a str, key: &str) -> Option<&'a str> {
+    let mut parts = info.strip_prefix('\\').unwrap_or(info).split('\\');
+
+    while let (Some(k), Some(v)) = (parts.next(), parts.next()) {
+        if k == key {
+            return Some(v);
+        }
+    }
+
+    None
+}
@@ -0,0 +1,309 @@
+//! 스트리밍 데모 리더
+//!
+//! 파일 전체를 메모리에 올리지 않고 프레임 단위로 읽어 DemoEvent 를 하나씩 돌려준다.
+//! 유지하는 상태는 파서 Aux, 엔티티 상태, 아직 붙지 않은 커멘드뿐이다.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::demo::{DemoEvent, DemoHeader, DirectoryEntry, frame_events};
+use crate::entity::EntityTracker;
+use crate::error::DemoParseError;
+use crate::parse::{
+    DIRECTORY_ENTRY_SIZE, FRAME_HEADER_SIZE, HEADER_SIZE, MsgDataParseMode,
+    NETWORK_MESSAGE_HEADER_SIZE, check_protocol, parse_directory, parse_frame,
+    parse_header_checked,
+};
+use crate::protocol::GameMod;
+use crate::types::{Aux, AuxRefCell, FrameData, MessageData};
+use crate::weapon::{Weapon, WeaponTracker};
+
+/// `Read + Seek` 에서 프레임을 하나씩 읽는 이터레이터
+///
+/// 첫 에러 이후에는 더 이상 읽지 않는다. 형식 에러는 InvalidData io::Error 안에 DemoParseError 로 들어 있다.
+///
+/// 이미 돌려준 프레임은 고칠 수 없으므로 처음 무기를 바꾸기 전 프레임의 `current_weapon` 은 None 으로 남는다.
+/// `demo::parse` 처럼 채우려면 그 뒤에 `held_before` 로 알게 된 무기를 쓴다.
+pub struct DemoReader<R: Read + Seek> {
+    reader: R,
+    header: DemoHeader,
+    directory: Vec<DirectoryEntry>,
+    /// 지금 읽고 있는 디렉토리 엔트리 인덱스
+    segment: usize,
+    /// 지금 세그먼트가 끝나는 위치. 다음 세그먼트, 디렉토리, 파일 끝 중 가장 가까운 곳이다.
+    segment_end: u64,
+    /// 파일 길이
+    length: u64,
+    aux: AuxRefCell,
+    entities: EntityTracker,
+    weapons: WeaponTracker,
+    commands_by_frame: HashMap<i32, Vec<String>>,
+    game_mod: GameMod,
+    /// 프레임 하나에서 나온 이벤트 중 아직 돌려주지 않은 것
+    pending: VecDeque<DemoEvent>,
+    /// 지금까지 읽은 프레임 수
+    frame_index: usize,
+    finished: bool,
+}
+
+impl<R: Read + Seek> DemoReader<R> {
+    /// 헤더와 디렉토리를 읽고 첫 세그먼트 시작으로 이동한다.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut bytes = vec![];
+        reader
+            .by_ref()
+            .take(HEADER_SIZE as u64)
+            .read_to_end(&mut bytes)?;
+
+        let header = parse_header_checked(&bytes)?;
+        check_protocol(&header)?;
+
+        let bad_directory = DemoParseError::BadDirectory {
+            offset: header.directory_offset.max(0) as usize,
+        };
+
+        let length = reader.seek(SeekFrom::End(0))?;
+        let directory_offset = u64::try_from(header.directory_offset)
+            .ok()
+            .filter(|&offset| offset >= HEADER_SIZE as u64 && offset + 4 <= length)
+            .ok_or_else(|| bad_directory.clone())?;
+
+        reader.seek(SeekFrom::Start(directory_offset))?;
+        let mut bytes = vec![0; 4];
+        reader.read_exact(&mut bytes)?;
+
+        // 엔트리 수가 남은 길이보다 크면 parse_directory 가 거부한다
+        let entry_count = last_i32(&bytes).ok_or_else(|| bad_directory.clone())?;
+        reader
+            .by_ref()
+            .take(entry_count as u64 * DIRECTORY_ENTRY_SIZE as u64)
+            .read_to_end(&mut bytes)?;
+
+        let directory = match parse_directory(&bytes) {
+            Ok((_, directory)) => directory,
+            Err(_) => return Err(bad_directory.into()),
+        };
+
+        let mut demo_reader = DemoReader {
+            reader,
+            header: DemoHeader::from(&header),
+            directory: directory.entries.iter().map(DirectoryEntry::from).collect(),
+            segment: 0,
+            segment_end: length,
+            length,
+            aux: Aux::new_ref_cell(),
+            entities: EntityTracker::new(),
+            weapons: WeaponTracker::new(),
+            commands_by_frame: HashMap::new(),
+            game_mod: GameMod::from_game_dir(&header.game_directory()),
+            pending: VecDeque::new(),
+            frame_index: 0,
+            finished: false,
+        };
+        demo_reader.seek_segment()?;
+
+        Ok(demo_reader)
+    }
+
+    pub fn header(&self) -> &DemoHeader {
+        &self.header
+    }
+
+    pub fn directory(&self) -> &[DirectoryEntry] {
+        &self.directory
+    }
+
+    /// 마지막으로 읽은 네트워크 메세지까지 반영된 엔티티 상태
+    pub fn entities(&self) -> &EntityTracker {
+        &self.entities
+    }
+
+    /// 녹화를 시작할 때부터 처음 무기를 바꿀 때까지 들고 있던 무기. 아직 바꾸지 않았으면 None
+    pub fn held_before(&self) -> Option<Weapon> {
+        self.weapons.held_before()
+    }
+
+    /// 현재 세그먼트의 시작 위치로 이동한다.
+    fn seek_segment(&mut self) -> io::Result<()> {
+        self.commands_by_frame.clear();
+
+        if let Some(entry) = self.directory.get(self.segment) {
+            let offset = u64::try_from(entry.offset).map_err(|_| DemoParseError::BadDirectory {
+                offset: self.header.dir_offset.max(0) as usize,
+            })?;
+            self.reader.seek(SeekFrom::Start(offset))?;
+
+            // parse_demo_checked 처럼 다음 세그먼트나 디렉토리가 시작하는 곳을 넘지 않는다
+            self.segment_end = self
+                .directory
+                .iter()
+                .map(|entry| entry.offset as i64)
+                .chain([self.header.dir_offset as i64])
+                .filter_map(|boundary| u64::try_from(boundary).ok())
+                .filter(|&boundary| boundary > offset)
+                .fold(self.length, u64::min);
+        }
+
+        Ok(())
+    }
+
+    /// 프레임 하나의 원본 바이트를 읽는다.
+    /// NextSection 없이 세그먼트 경계(다음 세그먼트, 디렉토리, 파일 끝)에 닿으면 None.
+    fn read_frame_bytes(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let offset = self.reader.stream_position()? as usize;
+        let frame_index = self.frame_index;
+        let truncated = DemoParseError::TruncatedFrame {
+            offset,
+            frame_index,
+        };
+        let invalid = DemoParseError::InvalidFrame {
+            offset,
+            frame_index,
+        };
+
+        if offset as u64 >= self.segment_end {
+            return Ok(None);
+        }
+
+        let mut bytes = vec![];
+        self.read_bytes(&mut bytes, FRAME_HEADER_SIZE, &truncated)?;
+
+        // 고정 길이 부분을 읽고, 끝의 길이 필드만큼 더 읽는다
+        let (fixed, variable) = match bytes[0] {
+            0 | 1 => (NETWORK_MESSAGE_HEADER_SIZE, 0),
+            2 | 5 => (0, 0),
+            3 => (64, 0),
+            4 => (32, 0),
+            6 => (84, 0),
+            7 => (8, 0),
+            8 => (8, 16),
+            9 => (4, 0),
+            frame_type => {
+                return Err(DemoParseError::UnknownFrameType {
+                    frame_type,
+                    offset,
+                    frame_index,
+                }
+                .into());
+            }
+        };
+
+        self.read_bytes(&mut bytes, fixed, &truncated)?;
+        if matches!(bytes[0], 0 | 1 | 8 | 9) {
+            let length = last_i32(&bytes).ok_or(invalid)?;
+            self.read_bytes(&mut bytes, length + variable, &truncated)?;
+        }
+
+        Ok(Some(bytes))
+    }
+
+    /// 길이 필드가 잘못되어도 미리 큰 버퍼를 잡지 않도록 take 로 읽는다.
+    /// 세그먼트 경계를 넘는 프레임은 잘린 프레임이다.
+    fn read_bytes(
+        &mut self,
+        bytes: &mut Vec<u8>,
+        length: usize,
+        truncated: &DemoParseError,
+    ) -> io::Result<()> {
+        let position = self.reader.stream_position()?;
+        if position + length as u64 > self.segment_end {
+            return Err(truncated.clone().into());
+        }
+
+        let read = self
+            .reader
+            .by_ref()
+            .take(length as u64)
+            .read_to_end(bytes)?;
+
+        if read != length {
+            return Err(truncated.clone().into());
+        }
+
+        Ok(())
+    }
+
+    fn read_events(&mut self) -> io::Result<Option<Vec<DemoEvent>>> {
+        loop {
+            if self.segment >= self.directory.len() {
+                return Ok(None);
+            }
+
+            let Some(bytes) = self.read_frame_bytes()? else {
+                self.segment += 1;
+                self.seek_segment()?;
+                continue;
+            };
+
+            let frame = match parse_frame(&bytes, MsgDataParseMode::Parse, &self.aux) {
+                Ok((_, frame)) => frame,
+                Err(_) => {
+                    let offset = self.reader.stream_position()? as usize - bytes.len();
+                    return Err(DemoParseError::InvalidFrame {
+                        offset,
+                        frame_index: self.frame_index,
+                    }
+                    .into());
+                }
+            };
+            self.frame_index += 1;
+
+            if let FrameData::NetworkMessage(message) = &frame.frame_data
+                && let MessageData::Parse(messages) = &message.1.messages
+            {
+                self.entities
+                    .update(message.1.sequence_info.incoming_sequence, messages);
+            }
+
+            let mut events = frame_events(
+                self.segment,
+                &frame,
+                &self.game_mod,
+                &mut self.commands_by_frame,
+            );
+            self.weapons.update(&mut events);
+
+            if matches!(frame.frame_data, FrameData::NextSection) {
+                self.segment += 1;
+                self.seek_segment()?;
+            }
+
+            return Ok(Some(events));
+        }
+    }
+}
+
+impl<R: Read + Seek> Iterator for DemoReader<R> {
+    type Item = io::Result<DemoEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+
+            if self.finished {
+                return None;
+            }
+
+            match self.read_events() {
+                Ok(Some(events)) => self.pending.extend(events),
+                Ok(None) => {
+                    self.finished = true;
+                    return None;
+                }
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// 방금 읽은 길이 필드. 음수면 None.
+fn last_i32(bytes: &[u8]) -> Option<usize> {
+    let length = i32::from_le_bytes(bytes[bytes.len() - 4..].try_into().unwrap());
+
+    usize::try_from(length).ok()
+}
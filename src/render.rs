@@ -85,7 +85,7 @@ pub fn render_slice_image(bsp: &BspData, center_z: f32, output: &str) {
             chart
                 .draw_series(std::iter::once(PathElement::new(
                     vec![(points[0][0], points[0][1]), (points[1][0], points[1][1])],
-                    &BLACK,
+                    BLACK,
                 )))
                 .unwrap();
         }
@@ -205,7 +205,7 @@ pub fn render_jump_cross_section(
             chart
                 .draw_series(std::iter::once(PathElement::new(
                     vec![(points[0][0], points[0][1]), (points[1][0], points[1][1])],
-                    &BLACK,
+                    BLACK,
                 )))
                 .unwrap();
         }
@@ -272,7 +272,7 @@ pub fn render_jump_cross_section(
                 (px, py),
                 (px + wish_dir.x * wish_len, py + wish_dir.y * wish_len),
             ],
-            &BLUE,
+            BLUE,
         )))
         .unwrap();
 
@@ -295,7 +295,7 @@ pub fn render_jump_cross_section(
                 (px, py),
                 (px + move_dir.x * move_len, py + move_dir.y * move_len),
             ],
-            &GREEN,
+            GREEN,
         )))
         .unwrap();
 
@@ -308,6 +308,7 @@ pub fn render_jump_cross_section(
 ///   - 이동 방향(simvel, 파란색)
 ///   - 시야/wish 방향(노란색)
 ///   - 지금까지의 궤적(trace, 빨간색)
+///
 ///   을 갱신해서 그린다.
 /// - 배경 맵은 점프 전체 궤적을 모두 포함하는 영역으로 고정된다.
 pub fn render_jump_gif(
@@ -377,7 +378,7 @@ pub fn render_jump_gif(
 
         {
             let root =
-                BitMapBackend::with_buffer(&mut buffer, (width as u32, height as u32))
+                BitMapBackend::with_buffer(&mut buffer, (width, height))
                     .into_drawing_area();
             root.fill(&WHITE)?;
 
@@ -438,7 +439,7 @@ pub fn render_jump_gif(
                 if points.len() == 2 {
                     chart.draw_series(std::iter::once(PathElement::new(
                         vec![(points[0][0], points[0][1]), (points[1][0], points[1][1])],
-                        &BLACK,
+                        BLACK,
                     )))?;
                 }
             }
@@ -488,7 +489,7 @@ pub fn render_jump_gif(
                     (px, py),
                     (px + wish_dir.x * wish_len, py + wish_dir.y * wish_len),
                 ],
-                &BLACK,
+                BLACK,
             )))?;
 
             // 이동 방향(simvel, 파란색)
@@ -509,7 +510,7 @@ pub fn render_jump_gif(
                     (px, py),
                     (px + move_dir.x * move_len, py + move_dir.y * move_len),
                 ],
-                &BLUE,
+                BLUE,
             )))?;
 
             root.present()?;
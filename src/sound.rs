@@ -0,0 +1,213 @@
+//! 사운드 프레임(타입 8) 타임라인
+//!
+//! 클라이언트가 예측해서 직접 낸 소리(발소리, 점프, 착지, 무기 소리)가 기록된다.
+//! 사운드 프레임은 같은 프레임 번호의 네트워크 메세지 프레임보다 먼저 기록되므로 그 프레임에 붙인다.
+
+use std::ops::Range;
+
+use crate::demo::{DemoEvent, DemoFrame, ParsedDemo};
+
+/// PM_PlayStepSound 가 고르는 재질별 발소리. 뒤에 번호가 붙는다 (player/pl_step1.wav)
+const STEP_SOUNDS: [&str; 10] = [
+    "player/pl_step",
+    "player/pl_metal",
+    "player/pl_dirt",
+    "player/pl_duct",
+    "player/pl_grate",
+    "player/pl_tile",
+    "player/pl_slosh",
+    "player/pl_wade",
+    "player/pl_ladder",
+    "player/pl_snow",
+];
+
+/// PM_Jump 가 내는 발소리 크기. 높은 곳에서 착지할 때(PM_CheckFalling)도 같은 크기이므로
+/// 땅을 떠나는 프레임의 소리인지도 함께 본다.
+const JUMP_VOLUME: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundKind {
+    /// 걷거나 뛰거나 착지할 때의 발소리
+    Footstep,
+    /// 점프할 때의 발소리. 소리 뒤의 프레임이나 그다음 프레임에서 onground 가 풀린다.
+    Jump,
+    /// 높은 곳에서 떨어져 다쳤을 때 (player/pl_fallpain*)
+    FallPain,
+    /// weapons/ 아래의 소리
+    Weapon,
+    Other,
+}
+
+impl SoundKind {
+    /// `takeoff` 는 소리가 난 때 땅을 떠났는지다.
+    fn classify(sample: &str, volume: f32, takeoff: bool) -> SoundKind {
+        let sample = sample.to_ascii_lowercase();
+
+        if STEP_SOUNDS.iter().any(|step| sample.starts_with(step)) {
+            if volume >= JUMP_VOLUME && takeoff {
+                SoundKind::Jump
+            } else {
+                SoundKind::Footstep
+            }
+        } else if sample.starts_with("player/pl_fallpain") {
+            SoundKind::FallPain
+        } else if sample.starts_with("weapons/") {
+            SoundKind::Weapon
+        } else {
+            SoundKind::Other
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundEvent {
+    pub time: f32,
+    pub frame: i32,
+    /// 소리 뒤에 오는 `ParsedDemo::frames` 인덱스. 마지막 프레임 뒤의 소리면 `frames.len()`
+    pub index: usize,
+    pub channel: i32,
+    pub sample: String,
+    pub volume: f32,
+    pub attenuation: f32,
+    pub flags: i32,
+    pub pitch: i32,
+    pub kind: SoundKind,
+}
+
+impl SoundEvent {
+    /// 발소리 재질의 소리 (점프, 착지 포함)
+    pub fn is_footstep(&self) -> bool {
+        matches!(self.kind, SoundKind::Footstep | SoundKind::Jump)
+    }
+}
+
+/// 공중에서 onground 가 된 프레임과 그때 난 소리
+#[derive(Debug, Clone, PartialEq)]
+pub struct Landing<'a> {
+    /// onground 가 된 `frames` 인덱스
+    pub index: usize,
+    pub frame: i32,
+    /// 착지 소리. 낮은 곳에서 떨어져 소리가 나지 않았으면 None
+    pub sound: Option<&'a SoundEvent>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SoundTimeline {
+    /// LOADING 세그먼트를 제외한 소리 (파일 순서)
+    pub sounds: Vec<SoundEvent>,
+}
+
+impl SoundTimeline {
+    pub fn from_demo(demo: &ParsedDemo) -> SoundTimeline {
+        let frames = &demo.frames;
+        // 점프 소리는 예측으로 먼저 나므로 onground 가 풀리는 프레임이나 그 직전 프레임 앞에 기록된다
+        let takeoff = |index: usize| {
+            (index.max(1)..frames.len().min(index + 2))
+                .any(|i| frames[i - 1].onground && !frames[i].onground)
+        };
+
+        let sounds = demo
+            .playback_events()
+            .filter_map(|(index, event)| match event {
+                DemoEvent::Sound(header, sound) => Some((index, header, sound)),
+                _ => None,
+            })
+            .map(|(index, header, sound)| {
+                let sample = sound.sample();
+
+                SoundEvent {
+                    time: header.time,
+                    frame: header.frame,
+                    index,
+                    channel: sound.channel,
+                    kind: SoundKind::classify(&sample, sound.volume, takeoff(index)),
+                    sample,
+                    volume: sound.volume,
+                    attenuation: sound.attenuation,
+                    flags: sound.flags,
+                    pitch: sound.pitch,
+                }
+            })
+            .collect();
+
+        SoundTimeline { sounds }
+    }
+
+    pub fn footsteps(&self) -> impl Iterator<Item = &SoundEvent> {
+        self.sounds.iter().filter(|sound| sound.is_footstep())
+    }
+
+    pub fn weapon_sounds(&self) -> impl Iterator<Item = &SoundEvent> {
+        self.sounds
+            .iter()
+            .filter(|sound| sound.kind == SoundKind::Weapon)
+    }
+
+    /// `frames` 인덱스 구간의 소리
+    pub fn between(&self, range: Range<usize>) -> &[SoundEvent] {
+        // 소리는 인덱스 순서로 쌓인다
+        let start = self
+            .sounds
+            .partition_point(|sound| sound.index < range.start);
+        let end = self.sounds.partition_point(|sound| sound.index < range.end);
+
+        &self.sounds[start..end.max(start)]
+    }
+
+    /// 구간 안에 발소리가 하나도 없으면 소리 없이 움직인 것이다.
+    pub fn is_silent(&self, range: Range<usize>) -> bool {
+        !self.between(range).iter().any(SoundEvent::is_footstep)
+    }
+
+    /// `frames` 에서 공중에 있다가 onground 가 된 프레임마다 착지 소리를 찾는다.
+    ///
+    /// 착지는 예측으로 먼저 소리가 나므로 onground 가 된 프레임이나 그 직전 프레임에 기록된다.
+    /// 착지하자마자 다시 뛰면 착지 소리도 점프로 분류될 수 있으므로, 다른 발소리가 없으면 점프 소리를 쓴다.
+    pub fn landings(&self, frames: &[DemoFrame]) -> Vec<Landing<'_>> {
+        (1..frames.len())
+            .filter(|&index| !frames[index - 1].onground && frames[index].onground)
+            .map(|index| {
+                let sounds = self.between(index - 1..index + 1);
+
+                Landing {
+                    index,
+                    frame: frames[index].frame,
+                    sound: sounds
+                        .iter()
+                        .find(|sound| {
+                            matches!(sound.kind, SoundKind::Footstep | SoundKind::FallPain)
+                        })
+                        .or_else(|| sounds.iter().find(|sound| sound.kind == SoundKind::Jump)),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::demo::parse;
+
+    #[test]
+    fn only_takeoff_steps_are_jumps() {
+        let demo = parse("test/274_dcj_Desu.dem").unwrap();
+        let timeline = SoundTimeline::from_demo(&demo);
+
+        // 139 프레임 앞에서 착지(0.5)와 점프(1.0) 발소리가 함께 나고 140 프레임에서 땅을 떠난다
+        let jumps: Vec<_> = timeline
+            .sounds
+            .iter()
+            .filter(|sound| sound.kind == SoundKind::Jump)
+            .map(|sound| (sound.index, sound.volume))
+            .collect();
+        assert_eq!(jumps, [(139, 1.0)]);
+
+        let landings = timeline.landings(&demo.frames);
+        let landing = landings
+            .iter()
+            .find(|landing| landing.index == 139)
+            .unwrap();
+        assert_eq!(landing.sound.unwrap().kind, SoundKind::Footstep);
+    }
+}
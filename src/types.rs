@@ -0,0 +1,978 @@
+//! 데모 파일 구조체 정의
+//!
+//! 고정 길이 문자열(맵 이름, 게임 디렉토리 등)은 널 종료 이후의 바이트까지
+//! 그대로 보관한다. 다시 쓸 때 원본과 같은 바이트를 만들기 위해서다.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+use crate::weapon::Weapon;
+
+/// 고정 길이 바이트 배열에서 첫 널 문자 이전까지를 문자열로 변환한다.
+pub fn bytes_to_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+#[derive(Debug, Clone)]
+pub struct Demo {
+    pub header: Header,
+    pub directory: Directory,
+}
+
+#[derive(Debug, Clone)]
+pub struct Header {
+    pub magic: Vec<u8>,
+    pub demo_protocol: i32,
+    pub network_protocol: i32,
+    pub map_name: Vec<u8>,
+    pub game_directory: Vec<u8>,
+    pub map_checksum: u32,
+    pub directory_offset: i32,
+}
+
+impl Header {
+    pub fn map_name(&self) -> String {
+        bytes_to_string(&self.map_name)
+    }
+
+    pub fn game_directory(&self) -> String {
+        bytes_to_string(&self.game_directory)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Directory {
+    pub entries: Vec<DirectoryEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DirectoryEntry {
+    pub type_: i32,
+    pub description: Vec<u8>,
+    pub flags: i32,
+    pub cd_track: i32,
+    pub track_time: f32,
+    pub frame_count: i32,
+    pub offset: i32,
+    pub file_length: i32,
+    pub frames: Vec<Frame>,
+    /// 마지막 프레임 뒤부터 다음 세그먼트(또는 디렉토리)까지 남은 원본 바이트. 엔진은 읽지 않는다.
+    pub trailing: Vec<u8>,
+}
+
+impl DirectoryEntry {
+    pub fn description(&self) -> String {
+        bytes_to_string(&self.description)
+    }
+}
+
+/// 모든 프레임이 공유하는 헤더(time/frame)와 타입별 데이터
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub time: f32,
+    pub frame: i32,
+    pub frame_data: FrameData,
+}
+
+#[derive(Debug, Clone)]
+pub enum FrameData {
+    /// 0, 1
+    NetworkMessage(Box<(NetworkMessageType, NetworkMessage)>),
+    /// 2
+    DemoStart,
+    /// 3
+    ConsoleCommand(ConsoleCommand),
+    /// 4
+    ClientData(ClientData),
+    /// 5
+    NextSection,
+    /// 6
+    Event(Event),
+    /// 7
+    WeaponAnimation(WeaponAnimation),
+    /// 8
+    Sound(Sound),
+    /// 9
+    DemoBuffer(DemoBuffer),
+}
+
+impl FrameData {
+    /// 파일에 기록되는 프레임 타입 번호
+    pub fn frame_type(&self) -> u8 {
+        match self {
+            FrameData::NetworkMessage(box_type) => match box_type.0 {
+                NetworkMessageType::Start => 0,
+                NetworkMessageType::Normal => 1,
+            },
+            FrameData::DemoStart => 2,
+            FrameData::ConsoleCommand(_) => 3,
+            FrameData::ClientData(_) => 4,
+            FrameData::NextSection => 5,
+            FrameData::Event(_) => 6,
+            FrameData::WeaponAnimation(_) => 7,
+            FrameData::Sound(_) => 8,
+            FrameData::DemoBuffer(_) => 9,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkMessageType {
+    /// LOADING 세그먼트의 서버 접속 정보
+    Start,
+    /// 재생 중 메세지
+    Normal,
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkMessage {
+    pub info: DemoInfo,
+    pub sequence_info: SequenceInfo,
+    pub message_length: i32,
+    pub messages: MessageData,
+}
+
+#[derive(Debug, Clone)]
+pub struct DemoInfo {
+    pub timestamp: f32,
+    pub ref_params: RefParams,
+    pub user_cmd: UserCmd,
+    pub movevars: MoveVars,
+    pub view: [f32; 3],
+    pub viewmodel: i32,
+}
+
+/// ref_params_t
+#[derive(Debug, Clone)]
+pub struct RefParams {
+    pub vieworg: [f32; 3],
+    pub viewangles: [f32; 3],
+    pub forward: [f32; 3],
+    pub right: [f32; 3],
+    pub up: [f32; 3],
+    pub frametime: f32,
+    pub time: f32,
+    pub intermission: i32,
+    pub paused: i32,
+    pub spectator: i32,
+    pub onground: i32,
+    pub waterlevel: i32,
+    pub simvel: [f32; 3],
+    pub simorg: [f32; 3],
+    pub viewheight: [f32; 3],
+    pub idealpitch: f32,
+    pub cl_viewangles: [f32; 3],
+    pub health: i32,
+    pub crosshairangle: [f32; 3],
+    pub viewsize: f32,
+    pub punchangle: [f32; 3],
+    pub maxclients: i32,
+    pub viewentity: i32,
+    pub playernum: i32,
+    pub max_entities: i32,
+    pub demoplayback: i32,
+    pub hardware: i32,
+    pub smoothing: i32,
+    pub ptr_cmd: i32,
+    pub ptr_movevars: i32,
+    pub viewport: [i32; 4],
+    pub next_view: i32,
+    pub only_client_draw: i32,
+}
+
+/// usercmd_t
+#[derive(Debug, Clone)]
+pub struct UserCmd {
+    pub lerp_msec: i16,
+    pub msec: u8,
+    pub align_1: u8,
+    pub viewangles: [f32; 3],
+    pub forwardmove: f32,
+    pub sidemove: f32,
+    pub upmove: f32,
+    pub lightlevel: i8,
+    pub align_2: u8,
+    pub buttons: u16,
+    pub impulse: i8,
+    pub weaponselect: i8,
+    pub align_3: u8,
+    pub align_4: u8,
+    pub impact_index: i32,
+    pub impact_position: [f32; 3],
+}
+
+/// movevars_t
+#[derive(Debug, Clone)]
+pub struct MoveVars {
+    pub gravity: f32,
+    pub stopspeed: f32,
+    pub maxspeed: f32,
+    pub spectatormaxspeed: f32,
+    pub accelerate: f32,
+    pub airaccelerate: f32,
+    pub wateraccelerate: f32,
+    pub friction: f32,
+    pub edgefriction: f32,
+    pub waterfriction: f32,
+    pub entgravity: f32,
+    pub bounce: f32,
+    pub stepsize: f32,
+    pub maxvelocity: f32,
+    pub zmax: f32,
+    pub wave_height: f32,
+    pub footsteps: i32,
+    pub sky_name: Vec<u8>,
+    pub rollangle: f32,
+    pub rollspeed: f32,
+    pub skycolor_r: f32,
+    pub skycolor_g: f32,
+    pub skycolor_b: f32,
+    pub skyvec: [f32; 3],
+}
+
+#[derive(Debug, Clone)]
+pub struct SequenceInfo {
+    pub incoming_sequence: i32,
+    pub incoming_acknowledged: i32,
+    pub incoming_reliable_acknowledged: i32,
+    pub incoming_reliable_sequence: i32,
+    pub outgoing_sequence: i32,
+    pub reliable_sequence: i32,
+    pub last_reliable_sequence: i32,
+}
+
+/// 네트워크 메세지 페이로드. 파싱 모드에 따라 보관 형태가 달라진다.
+#[derive(Debug, Clone)]
+pub enum MessageData {
+    Parse(Vec<NetMessage>),
+    Raw(Vec<u8>),
+    None,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConsoleCommand {
+    pub command: Vec<u8>,
+}
+
+impl ConsoleCommand {
+    pub fn command(&self) -> String {
+        bytes_to_string(&self.command)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientData {
+    pub origin: [f32; 3],
+    pub viewangles: [f32; 3],
+    /// 가진 무기. 무기 번호가 비트 번호다 (31 번은 HEV 수트)
+    pub weapon_bits: i32,
+    pub fov: f32,
+}
+
+impl ClientData {
+    pub fn weapons(&self) -> impl Iterator<Item = Weapon> {
+        (0..32)
+            .filter(|bit| self.weapon_bits as u32 & (1 << bit) != 0)
+            .filter_map(Weapon::from_id)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub flags: i32,
+    pub index: i32,
+    pub delay: f32,
+    pub args: EventArgs,
+}
+
+/// event_args_t
+#[derive(Debug, Clone)]
+pub struct EventArgs {
+    pub flags: i32,
+    pub entity_index: i32,
+    pub origin: [f32; 3],
+    pub angles: [f32; 3],
+    pub velocity: [f32; 3],
+    pub ducking: i32,
+    pub fparam1: f32,
+    pub fparam2: f32,
+    pub iparam1: i32,
+    pub iparam2: i32,
+    pub bparam1: i32,
+    pub bparam2: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct WeaponAnimation {
+    pub anim: i32,
+    pub body: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sound {
+    pub channel: i32,
+    pub sample: Vec<u8>,
+    /// 파일에는 volume 이 attenuation 보다 먼저 기록된다
+    pub volume: f32,
+    pub attenuation: f32,
+    pub flags: i32,
+    pub pitch: i32,
+}
+
+impl Sound {
+    pub fn sample(&self) -> String {
+        bytes_to_string(&self.sample)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DemoBuffer {
+    pub buffer: Vec<u8>,
+}
+
+/// svc_* 네트워크 메세지
+#[derive(Debug, Clone)]
+pub enum NetMessage {
+    UserMessage(UserMessage),
+    EngineMessage(Box<EngineMessage>),
+    /// 아직 해석하지 못하는 메세지부터 페이로드 끝까지의 원본 바이트
+    Unparsed(Vec<u8>),
+}
+
+/// svc_newusermsg 로 등록된 사용자 메세지
+#[derive(Debug, Clone)]
+pub struct UserMessage {
+    pub id: u8,
+    pub name: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+impl UserMessage {
+    pub fn name(&self) -> String {
+        bytes_to_string(&self.name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum EngineMessage {
+    SvcBad,
+    SvcNop,
+    SvcDisconnect(SvcDisconnect),
+    SvcEvent(SvcEvent),
+    SvcVersion(SvcVersion),
+    SvcSetView(SvcSetView),
+    SvcSound(SvcSound),
+    SvcTime(SvcTime),
+    SvcPrint(SvcPrint),
+    SvcStuffText(SvcStuffText),
+    SvcSetAngle(SvcSetAngle),
+    SvcServerInfo(SvcServerInfo),
+    SvcLightStyle(SvcLightStyle),
+    SvcUpdateUserInfo(SvcUpdateUserInfo),
+    SvcDeltaDescription(SvcDeltaDescription),
+    SvcClientData(SvcClientData),
+    SvcStopSound(SvcStopSound),
+    SvcPings(SvcPings),
+    SvcParticle(SvcParticle),
+    SvcSpawnStatic(SvcSpawnStatic),
+    SvcEventReliable(SvcEventReliable),
+    SvcSpawnBaseline(SvcSpawnBaseline),
+    SvcTempEntity(SvcTempEntity),
+    SvcSetPause(SvcSetPause),
+    SvcSignOnNum(SvcSignOnNum),
+    SvcCenterPrint(SvcCenterPrint),
+    SvcKilledMonster,
+    SvcFoundSecret,
+    SvcSpawnStaticSound(SvcSpawnStaticSound),
+    SvcIntermission,
+    SvcFinale(SvcFinale),
+    SvcCdTrack(SvcCdTrack),
+    SvcRestore(SvcRestore),
+    SvcCutscene(SvcCutscene),
+    SvcWeaponAnim(SvcWeaponAnim),
+    SvcDecalName(SvcDecalName),
+    SvcRoomType(SvcRoomType),
+    SvcAddAngle(SvcAddAngle),
+    SvcNewUserMsg(SvcNewUserMsg),
+    SvcPacketEntities(SvcPacketEntities),
+    SvcDeltaPacketEntities(SvcDeltaPacketEntities),
+    SvcChoke,
+    SvcResourceList(SvcResourceList),
+    SvcNewMoveVars(SvcNewMoveVars),
+    SvcResourceRequest(SvcResourceRequest),
+    SvcCustomization(SvcCustomization),
+    SvcCrosshairAngle(SvcCrosshairAngle),
+    SvcSoundFade(SvcSoundFade),
+    SvcFileTxferFailed(SvcFileTxferFailed),
+    SvcHltv(SvcHltv),
+    SvcDirector(SvcDirector),
+    SvcVoiceInit(SvcVoiceInit),
+    SvcVoiceData(SvcVoiceData),
+    SvcSendExtraInfo(SvcSendExtraInfo),
+    SvcTimeScale(SvcTimeScale),
+    SvcResourceLocation(SvcResourceLocation),
+    SvcSendCvarValue(SvcSendCvarValue),
+    SvcSendCvarValue2(SvcSendCvarValue2),
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcDisconnect {
+    pub reason: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcEvent {
+    pub events: Vec<EventS>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EventS {
+    pub event_index: u16,
+    pub packet_index: Option<u16>,
+    /// packet_index 가 있을 때만 기록되는 플래그
+    pub has_delta: Option<bool>,
+    pub delta: Option<Delta>,
+    /// 1/100 초 단위
+    pub fire_time: Option<u16>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcVersion {
+    pub protocol_version: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcSetView {
+    pub entity_index: i16,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcSound {
+    pub flags: u16,
+    pub volume: Option<u8>,
+    /// attenuation * 64
+    pub attenuation: Option<u8>,
+    pub channel: u8,
+    pub entity_index: u16,
+    pub sound_index: u16,
+    pub origin: [f32; 3],
+    pub pitch: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcTime {
+    pub time: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcPrint {
+    pub message: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcStuffText {
+    pub command: Vec<u8>,
+}
+
+/// hires angle(16비트) 원본 값
+#[derive(Debug, Clone)]
+pub struct SvcSetAngle {
+    pub pitch: i16,
+    pub yaw: i16,
+    pub roll: i16,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcServerInfo {
+    pub protocol: i32,
+    pub spawn_count: i32,
+    pub map_checksum: i32,
+    pub client_dll_hash: Vec<u8>,
+    pub max_players: u8,
+    pub player_index: u8,
+    pub is_deathmatch: u8,
+    pub game_dir: Vec<u8>,
+    pub hostname: Vec<u8>,
+    pub map_file_name: Vec<u8>,
+    pub map_cycle: Vec<u8>,
+    /// 프로토콜 47 부터 붙는 바이트
+    pub unknown: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcLightStyle {
+    pub index: u8,
+    pub light_info: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcUpdateUserInfo {
+    pub index: u8,
+    pub id: u32,
+    pub user_info: Vec<u8>,
+    pub cd_key_hash: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcDeltaDescription {
+    pub name: Vec<u8>,
+    pub total_fields: u16,
+    pub fields: Vec<DeltaDecoderField>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcClientData {
+    /// 델타 기준 프레임 (delta_sequence)
+    pub delta_update_mask: Option<u8>,
+    pub client_data: Delta,
+    pub weapon_data: Vec<ClientDataWeaponData>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientDataWeaponData {
+    pub weapon_index: u8,
+    pub weapon_data: Delta,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcStopSound {
+    pub entity_index: i16,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcPings {
+    pub pings: Vec<PingS>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PingS {
+    pub player_id: u8,
+    pub ping: u16,
+    pub loss: u8,
+}
+
+/// 좌표는 MSG_WriteCoord(1/8 유닛 정수) 원본 값
+#[derive(Debug, Clone)]
+pub struct SvcParticle {
+    pub origin: [i16; 3],
+    pub direction: [i8; 3],
+    pub count: u8,
+    pub color: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcSpawnStatic {
+    pub model_index: i16,
+    pub sequence: u8,
+    pub frame: u8,
+    pub color_map: u16,
+    pub skin: u8,
+    pub origin_x: i16,
+    pub rotation_x: i8,
+    pub origin_y: i16,
+    pub rotation_y: i8,
+    pub origin_z: i16,
+    pub rotation_z: i8,
+    pub render_mode: u8,
+    pub render_amt: Option<u8>,
+    pub render_color: Option<[u8; 3]>,
+    pub render_fx: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcEventReliable {
+    pub event_index: u16,
+    pub event_args: Delta,
+    /// 1/100 초 단위
+    pub fire_time: Option<u16>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcSpawnBaseline {
+    pub entities: Vec<BaselineEntity>,
+    /// 인스턴스 베이스라인 (entity_state_t)
+    pub extra_data: Vec<Delta>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BaselineEntity {
+    pub index: u16,
+    /// 1 = ENTITY_NORMAL, 2 = ENTITY_BEAM
+    pub type_: u8,
+    pub delta: Delta,
+}
+
+/// TE_* 종류와 해당 엔티티의 원본 데이터
+#[derive(Debug, Clone)]
+pub struct SvcTempEntity {
+    pub entity_type: u8,
+    pub entity: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcSetPause {
+    pub is_paused: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcSignOnNum {
+    pub sign: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcCenterPrint {
+    pub message: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcSpawnStaticSound {
+    pub origin: [i16; 3],
+    pub sound_index: u16,
+    pub volume: u8,
+    pub attenuation: u8,
+    pub entity_index: u16,
+    pub pitch: u8,
+    pub flags: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcFinale {
+    pub text: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcCdTrack {
+    pub track: u8,
+    pub loop_track: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcRestore {
+    pub save_name: Vec<u8>,
+    pub maps: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcCutscene {
+    pub text: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcWeaponAnim {
+    pub sequence_number: u8,
+    pub weapon_model_body_group: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcDecalName {
+    pub position_index: u8,
+    pub decal_name: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcRoomType {
+    pub room_type: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcAddAngle {
+    pub angle_to_add: i16,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcNewUserMsg {
+    pub index: u8,
+    /// 255 는 가변 길이(첫 바이트가 길이)
+    pub size: u8,
+    pub name: Vec<u8>,
+}
+
+impl SvcNewUserMsg {
+    pub fn name(&self) -> String {
+        bytes_to_string(&self.name)
+    }
+}
+
+/// 엔티티 번호 헤더. 이전 엔티티 번호와의 차이로 기록된다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityIndexHeader {
+    /// 이전 번호 + 1 (svc_packetentities 에만 있음)
+    Increment,
+    /// 11비트 절대값
+    Absolute(u16),
+    /// 6비트 차이
+    Difference(u8),
+}
+
+#[derive(Debug, Clone)]
+pub struct PacketEntity {
+    pub entity_index: u16,
+    pub index_header: EntityIndexHeader,
+    pub has_custom_delta: bool,
+    /// 인스턴스 베이스라인이 있을 때만 기록된다.
+    pub baseline_index: Option<Option<u8>>,
+    /// svc_packetentities 에서 인스턴스 베이스라인을 쓰지 않을 때만 기록된다.
+    /// 같은 패킷에서 (entity_index - offset) 엔티티를 기준으로 삼는다.
+    pub baseline_offset: Option<Option<u8>>,
+    pub delta: Delta,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcPacketEntities {
+    pub entity_count: u16,
+    pub entities: Vec<PacketEntity>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeltaPacketEntity {
+    pub entity_index: u16,
+    pub index_header: EntityIndexHeader,
+    pub remove_entity: bool,
+    pub has_custom_delta: Option<bool>,
+    pub baseline_index: Option<Option<u8>>,
+    pub delta: Option<Delta>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcDeltaPacketEntities {
+    pub entity_count: u16,
+    pub delta_sequence: u8,
+    pub entities: Vec<DeltaPacketEntity>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcResourceList {
+    pub resources: Vec<Resource>,
+    /// 일관성 검사 목록 (리소스 인덱스)
+    pub consistencies: Option<Vec<Consistency>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Resource {
+    /// t_sound 0, t_skin 1, t_model 2, t_decal 3, t_generic 4, t_eventscript 5, t_world 6
+    pub type_: u8,
+    pub name: Vec<u8>,
+    pub index: u16,
+    pub size: u32,
+    pub flags: u8,
+    pub md5_hash: Option<Vec<u8>>,
+    pub extra_info: Option<Vec<u8>>,
+}
+
+impl Resource {
+    pub fn name(&self) -> String {
+        bytes_to_string(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Consistency {
+    /// 이전 인덱스와의 5비트 차이
+    Short(u8),
+    /// 10비트 절대 인덱스
+    Long(u16),
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcNewMoveVars {
+    pub gravity: f32,
+    pub stop_speed: f32,
+    pub max_speed: f32,
+    pub spectator_max_speed: f32,
+    pub accelerate: f32,
+    pub airaccelerate: f32,
+    pub water_accelerate: f32,
+    pub friction: f32,
+    pub edge_friction: f32,
+    pub water_friction: f32,
+    pub ent_gravity: f32,
+    pub bounce: f32,
+    pub step_size: f32,
+    pub max_velocity: f32,
+    pub z_max: f32,
+    pub wave_height: f32,
+    pub footsteps: u8,
+    pub roll_angle: f32,
+    pub roll_speed: f32,
+    pub sky_color: [f32; 3],
+    pub sky_vec: [f32; 3],
+    pub sky_name: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcResourceRequest {
+    pub spawn_count: i32,
+    pub unknown: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcCustomization {
+    pub player_index: u8,
+    pub type_: u8,
+    pub name: Vec<u8>,
+    pub index: u16,
+    pub download_size: u32,
+    pub flags: u8,
+    pub md5_hash: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcCrosshairAngle {
+    pub pitch: i8,
+    pub yaw: i8,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcSoundFade {
+    pub initial_percent: u8,
+    pub hold_time: u8,
+    pub fade_out_time: u8,
+    pub fade_in_time: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcFileTxferFailed {
+    pub file_name: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SvcHltv {
+    Active,
+    /// HLTV_STATUS 의 원본 18바이트
+    Status(Vec<u8>),
+    Listen(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcDirector {
+    pub length: u8,
+    pub command: u8,
+    pub message: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcVoiceInit {
+    pub codec_name: Vec<u8>,
+    pub quality: i8,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcVoiceData {
+    pub player_index: u8,
+    pub size: u16,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcSendExtraInfo {
+    pub fallback_dir: Vec<u8>,
+    pub can_cheat: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcTimeScale {
+    pub time_scale: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcResourceLocation {
+    pub sv_downloadurl: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcSendCvarValue {
+    pub name: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvcSendCvarValue2 {
+    pub request_id: u32,
+    pub name: Vec<u8>,
+}
+
+pub const DT_BYTE: u32 = 1 << 0;
+pub const DT_SHORT: u32 = 1 << 1;
+pub const DT_FLOAT: u32 = 1 << 2;
+pub const DT_INTEGER: u32 = 1 << 3;
+pub const DT_ANGLE: u32 = 1 << 4;
+pub const DT_TIMEWINDOW_8: u32 = 1 << 5;
+pub const DT_TIMEWINDOW_BIG: u32 = 1 << 6;
+pub const DT_STRING: u32 = 1 << 7;
+pub const DT_SIGNED: u32 = 1 << 31;
+
+/// svc_deltadescription 으로 전달되는 필드 하나의 인코딩 방식
+#[derive(Debug, Clone)]
+pub struct DeltaDecoderField {
+    pub name: Vec<u8>,
+    /// DT_* 플래그
+    pub flags: u32,
+    pub offset: u16,
+    pub size: u8,
+    pub bits: u8,
+    pub pre_multiplier: f32,
+    pub post_multiplier: f32,
+}
+
+impl DeltaDecoderField {
+    pub fn name(&self) -> String {
+        bytes_to_string(&self.name)
+    }
+}
+
+/// 필드 순서가 곧 비트마스크 순서다.
+pub type DeltaDecoder = Vec<DeltaDecoderField>;
+pub type DeltaDecoderTable = HashMap<String, DeltaDecoder>;
+
+/// 델타로 전달된 필드 값
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeltaValue {
+    /// 비트에서 읽은 원본 정수(부호 적용)와 배율을 적용한 값
+    Number { raw: i64, value: f64 },
+    String(Vec<u8>),
+}
+
+impl DeltaValue {
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            DeltaValue::Number { value, .. } => Some(*value as f32),
+            DeltaValue::String(_) => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            DeltaValue::Number { value, .. } => Some(*value as i32),
+            DeltaValue::String(_) => None,
+        }
+    }
+}
+
+/// 필드 이름 -> 값. 비트마스크에 포함된 필드만 들어 있다.
+pub type Delta = BTreeMap<String, DeltaValue>;
+
+/// 네트워크 메세지 파싱 중 유지해야 하는 상태
+#[derive(Debug, Clone, Default)]
+pub struct Aux {
+    /// svc_newusermsg 로 등록된 사용자 메세지
+    pub custom_messages: HashMap<u8, SvcNewUserMsg>,
+    /// svc_deltadescription 으로 받은 델타 디코더
+    pub delta_decoders: DeltaDecoderTable,
+    /// svc_serverinfo 의 max_players
+    pub max_client: u8,
+    /// svc_spawnbaseline 의 인스턴스 베이스라인 수
+    pub instanced_baseline_count: u8,
+    /// HLTV 로 받은 스트림인지 (ref_params.spectator). svc_clientdata 본문이 비어 있다.
+    pub is_hltv: bool,
+}
+
+impl Aux {
+    pub fn new_ref_cell() -> AuxRefCell {
+        Rc::new(RefCell::new(Self::default()))
+    }
+}
+
+pub type AuxRefCell = Rc<RefCell<Aux>>;
@@ -0,0 +1,258 @@
+//! CS 1.6 사용자 메세지(usermsg) 해석 모듈
+//!
+//! 사용자 메세지 번호는 서버마다 다르므로 svc_newusermsg 로 등록된 이름으로 형식을 고른다.
+
+use nom::{
+    combinator::{all_consuming, map, rest},
+    multi::many0,
+    number::complete::{le_i8, le_i16, le_i32, le_u8},
+    sequence::tuple,
+};
+
+use crate::{
+    nom_helper::{Result, null_string, take_point_coord},
+    types::{UserMessage, bytes_to_string},
+};
+
+/// 해석한 사용자 메세지
+#[derive(Debug, Clone)]
+pub enum CsUserMessage {
+    SayText(SayText),
+    TextMsg(TextMsg),
+    DeathMsg(DeathMsg),
+    ResetHud,
+    Health(Health),
+    Battery(Battery),
+    Damage(Damage),
+    CurWeapon(CurWeapon),
+    Money(Money),
+    RoundTime(RoundTime),
+    StatusText(StatusText),
+    StatusValue(StatusValue),
+    ScoreInfo(ScoreInfo),
+    TeamInfo(TeamInfo),
+    /// 형식을 모르거나 해석에 실패한 메세지
+    Other(UserMessage),
+}
+
+/// 채팅. `message` 는 `#Cstrike_Chat_All` 같은 번역 키이거나 완성된 문자열이다.
+#[derive(Debug, Clone)]
+pub struct SayText {
+    /// 말한 플레이어의 엔티티 번호 (0 이면 서버)
+    pub sender: u8,
+    pub message: String,
+    /// 번역 키에 들어갈 인자 (보통 이름, 본문)
+    pub params: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TextMsg {
+    /// HUD_PRINTNOTIFY 1, HUD_PRINTCONSOLE 2, HUD_PRINTTALK 3, HUD_PRINTCENTER 4
+    pub destination: u8,
+    pub message: String,
+    pub params: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeathMsg {
+    /// 엔티티 번호. 월드에 죽으면 0
+    pub killer: u8,
+    pub victim: u8,
+    pub headshot: bool,
+    /// weapon_ 접두사가 빠진 무기 이름 (예: ak47, grenade, worldspawn)
+    pub weapon: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Health {
+    pub health: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct Battery {
+    pub armor: i16,
+}
+
+#[derive(Debug, Clone)]
+pub struct Damage {
+    pub armor: u8,
+    pub damage: u8,
+    /// DMG_* 비트
+    pub damage_bits: i32,
+    /// 피해를 준 위치 (MSG_WriteCoord 원본 값)
+    pub origin: [i16; 3],
+}
+
+#[derive(Debug, Clone)]
+pub struct CurWeapon {
+    /// 0 이면 무기 목록 갱신, 1 이면 현재 무기
+    pub state: u8,
+    /// WEAPON_* 번호
+    pub weapon_id: u8,
+    /// 탄창이 없는 무기는 -1
+    pub clip: i8,
+}
+
+#[derive(Debug, Clone)]
+pub struct Money {
+    pub amount: i32,
+    /// 증감 표시 여부
+    pub flash: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct RoundTime {
+    /// 남은 라운드 시간(초)
+    pub seconds: i16,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatusText {
+    pub line: u8,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatusValue {
+    pub index: u8,
+    pub value: i16,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScoreInfo {
+    /// 엔티티 번호
+    pub player: u8,
+    pub frags: i16,
+    pub deaths: i16,
+    pub class_id: i16,
+    pub team_id: i16,
+}
+
+#[derive(Debug, Clone)]
+pub struct TeamInfo {
+    /// 엔티티 번호
+    pub player: u8,
+    /// TERRORIST, CT, SPECTATOR, UNASSIGNED
+    pub team: String,
+}
+
+impl CsUserMessage {
+    /// 등록된 이름으로 형식을 골라 해석한다. 실패하면 `Other` 로 원본을 남긴다.
+    pub fn parse(message: &UserMessage) -> CsUserMessage {
+        let name = message.name();
+        let data = message.data.as_slice();
+
+        let parsed = match name.as_str() {
+            "SayText" => map(parse_say_text, CsUserMessage::SayText)(data),
+            "TextMsg" => map(parse_text_msg, CsUserMessage::TextMsg)(data),
+            "DeathMsg" => map(parse_death_msg, CsUserMessage::DeathMsg)(data),
+            "ResetHUD" => map(rest, |_| CsUserMessage::ResetHud)(data),
+            "Health" => map(le_u8, |health| CsUserMessage::Health(Health { health }))(data),
+            "Battery" => map(le_i16, |armor| CsUserMessage::Battery(Battery { armor }))(data),
+            "Damage" => map(parse_damage, CsUserMessage::Damage)(data),
+            "CurWeapon" => map(parse_cur_weapon, CsUserMessage::CurWeapon)(data),
+            "Money" => map(tuple((le_i32, le_u8)), |(amount, flash)| {
+                CsUserMessage::Money(Money {
+                    amount,
+                    flash: flash != 0,
+                })
+            })(data),
+            "RoundTime" => map(le_i16, |seconds| {
+                CsUserMessage::RoundTime(RoundTime { seconds })
+            })(data),
+            "StatusText" => map(tuple((le_u8, null_string)), |(line, text)| {
+                CsUserMessage::StatusText(StatusText {
+                    line,
+                    text: bytes_to_string(&text),
+                })
+            })(data),
+            "StatusValue" => map(tuple((le_u8, le_i16)), |(index, value)| {
+                CsUserMessage::StatusValue(StatusValue { index, value })
+            })(data),
+            "ScoreInfo" => map(parse_score_info, CsUserMessage::ScoreInfo)(data),
+            "TeamInfo" => map(tuple((le_u8, null_string)), |(player, team)| {
+                CsUserMessage::TeamInfo(TeamInfo {
+                    player,
+                    team: bytes_to_string(&team),
+                })
+            })(data),
+            _ => return CsUserMessage::Other(message.clone()),
+        };
+
+        match parsed {
+            Ok((_, parsed)) => parsed,
+            Err(_) => CsUserMessage::Other(message.clone()),
+        }
+    }
+}
+
+/// 메세지 끝까지 이어지는 널 종료 문자열들
+fn strings(i: &[u8]) -> Result<'_, Vec<String>> {
+    map(all_consuming(many0(null_string)), |strings| {
+        strings.iter().map(|s| bytes_to_string(s)).collect()
+    })(i)
+}
+
+fn parse_say_text(i: &[u8]) -> Result<'_, SayText> {
+    map(tuple((le_u8, null_string, strings)), |(sender, message, params)| SayText {
+        sender,
+        message: bytes_to_string(&message),
+        params,
+    })(i)
+}
+
+fn parse_text_msg(i: &[u8]) -> Result<'_, TextMsg> {
+    map(
+        tuple((le_u8, null_string, strings)),
+        |(destination, message, params)| TextMsg {
+            destination,
+            message: bytes_to_string(&message),
+            params,
+        },
+    )(i)
+}
+
+fn parse_death_msg(i: &[u8]) -> Result<'_, DeathMsg> {
+    map(
+        tuple((le_u8, le_u8, le_u8, null_string)),
+        |(killer, victim, headshot, weapon)| DeathMsg {
+            killer,
+            victim,
+            headshot: headshot != 0,
+            weapon: bytes_to_string(&weapon),
+        },
+    )(i)
+}
+
+fn parse_damage(i: &[u8]) -> Result<'_, Damage> {
+    map(
+        tuple((le_u8, le_u8, le_i32, take_point_coord)),
+        |(armor, damage, damage_bits, origin)| Damage {
+            armor,
+            damage,
+            damage_bits,
+            origin,
+        },
+    )(i)
+}
+
+fn parse_cur_weapon(i: &[u8]) -> Result<'_, CurWeapon> {
+    map(tuple((le_u8, le_u8, le_i8)), |(state, weapon_id, clip)| CurWeapon {
+        state,
+        weapon_id,
+        clip,
+    })(i)
+}
+
+fn parse_score_info(i: &[u8]) -> Result<'_, ScoreInfo> {
+    map(
+        tuple((le_u8, le_i16, le_i16, le_i16, le_i16)),
+        |(player, frags, deaths, class_id, team_id)| ScoreInfo {
+            player,
+            frags,
+            deaths,
+            class_id,
+            team_id,
+        },
+    )(i)
+}
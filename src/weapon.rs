@@ -0,0 +1,235 @@
+//! CS 1.6 무기 구분과 녹화한 플레이어의 현재 무기 추적
+//!
+//! 들고 있는 무기가 최대 이동 속도(pmove->maxspeed)를 정하므로 점프 기록의 유효성을 따질 때 필요하다.
+
+use crate::demo::DemoEvent;
+use crate::usermsg::{CsUserMessage, CurWeapon};
+
+/// CS 1.6 무기 번호 (WEAPON_*). CurWeapon 과 usercmd weaponselect 가 이 번호를 쓴다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Weapon {
+    P228 = 1,
+    Scout = 3,
+    HeGrenade = 4,
+    Xm1014 = 5,
+    C4 = 6,
+    Mac10 = 7,
+    Aug = 8,
+    SmokeGrenade = 9,
+    Elite = 10,
+    FiveSeven = 11,
+    Ump45 = 12,
+    Sg550 = 13,
+    Galil = 14,
+    Famas = 15,
+    Usp = 16,
+    Glock18 = 17,
+    Awp = 18,
+    Mp5Navy = 19,
+    M249 = 20,
+    M3 = 21,
+    M4a1 = 22,
+    Tmp = 23,
+    G3sg1 = 24,
+    Flashbang = 25,
+    Deagle = 26,
+    Sg552 = 27,
+    Ak47 = 28,
+    Knife = 29,
+    P90 = 30,
+}
+
+/// 무기, weapon_ 접두사가 빠진 이름, 조준하지 않았을 때의 최대 속도
+const WEAPONS: [(Weapon, &str, f32); 29] = [
+    (Weapon::P228, "p228", 250.0),
+    (Weapon::Scout, "scout", 260.0),
+    (Weapon::HeGrenade, "hegrenade", 250.0),
+    (Weapon::Xm1014, "xm1014", 240.0),
+    (Weapon::C4, "c4", 250.0),
+    (Weapon::Mac10, "mac10", 250.0),
+    (Weapon::Aug, "aug", 240.0),
+    (Weapon::SmokeGrenade, "smokegrenade", 250.0),
+    (Weapon::Elite, "elite", 250.0),
+    (Weapon::FiveSeven, "fiveseven", 250.0),
+    (Weapon::Ump45, "ump45", 250.0),
+    (Weapon::Sg550, "sg550", 210.0),
+    (Weapon::Galil, "galil", 240.0),
+    (Weapon::Famas, "famas", 240.0),
+    (Weapon::Usp, "usp", 250.0),
+    (Weapon::Glock18, "glock18", 250.0),
+    (Weapon::Awp, "awp", 210.0),
+    (Weapon::Mp5Navy, "mp5navy", 250.0),
+    (Weapon::M249, "m249", 220.0),
+    (Weapon::M3, "m3", 230.0),
+    (Weapon::M4a1, "m4a1", 230.0),
+    (Weapon::Tmp, "tmp", 250.0),
+    (Weapon::G3sg1, "g3sg1", 210.0),
+    (Weapon::Flashbang, "flashbang", 250.0),
+    (Weapon::Deagle, "deagle", 250.0),
+    (Weapon::Sg552, "sg552", 235.0),
+    (Weapon::Ak47, "ak47", 221.0),
+    (Weapon::Knife, "knife", 250.0),
+    (Weapon::P90, "p90", 245.0),
+];
+
+impl Weapon {
+    pub fn from_id(id: u8) -> Option<Weapon> {
+        WEAPONS
+            .iter()
+            .find(|(weapon, _, _)| weapon.id() == id)
+            .map(|&(weapon, _, _)| weapon)
+    }
+
+    /// DeathMsg 의 무기 이름처럼 weapon_ 접두사가 빠진 이름으로 찾는다.
+    pub fn from_name(name: &str) -> Option<Weapon> {
+        let name = name.strip_prefix("weapon_").unwrap_or(name);
+
+        WEAPONS
+            .iter()
+            .find(|(_, weapon_name, _)| weapon_name.eq_ignore_ascii_case(name))
+            .map(|&(weapon, _, _)| weapon)
+    }
+
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn name(self) -> &'static str {
+        self.entry().1
+    }
+
+    /// 조준경을 쓰지 않을 때의 최대 이동 속도 (GetMaxSpeed)
+    pub fn maxspeed(self) -> f32 {
+        self.entry().2
+    }
+
+    fn entry(self) -> &'static (Weapon, &'static str, f32) {
+        WEAPONS
+            .iter()
+            .find(|(weapon, _, _)| *weapon == self)
+            .unwrap()
+    }
+}
+
+/// 프레임 이벤트를 따라가며 NetworkMessage 이벤트의 DemoFrame 에 `current_weapon` 을 채운다.
+///
+/// CurWeapon 을 기준으로 삼고, 그 사이에는 usercmd 의 weaponselect 로 고른 무기가
+/// 꺼내는 애니메이션(타입 7 프레임)과 함께 바뀐 것으로 본다.
+///
+/// 서버는 클라이언트에 이미 보낸 무기 상태를 다시 보내지 않으므로 녹화를 시작할 때 들고 있던 무기는
+/// 처음 무기를 바꿀 때 내려놓는 CurWeapon(state 0)으로만 알 수 있다. 그때까지 `current_weapon` 은
+/// 알 수 없다는 뜻의 None 이고, 내려놓은 무기는 `held_before` 로 남는다.
+#[derive(Debug, Default)]
+pub struct WeaponTracker {
+    current: Option<Weapon>,
+    /// 골랐지만 아직 꺼내는 애니메이션이나 CurWeapon 이 오지 않은 무기
+    selected: Option<Weapon>,
+    /// 처음 무기를 알게 된 프레임에서 내려놓은 무기
+    held_before: Option<Weapon>,
+    /// `current` 가 한 번이라도 정해졌는지
+    known: bool,
+}
+
+impl WeaponTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 녹화를 시작할 때부터 처음 무기를 알게 될 때까지 들고 있던 무기.
+    /// 지나간 프레임은 고칠 수 없으므로 `demo::parse` 가 그 프레임들에 채운다.
+    pub fn held_before(&self) -> Option<Weapon> {
+        self.held_before
+    }
+
+    /// 프레임 하나에서 나온 이벤트를 반영한다.
+    pub fn update(&mut self, events: &mut [DemoEvent]) {
+        let mut released = None;
+
+        for event in events.iter() {
+            match event {
+                DemoEvent::NetworkMessage(_, frame) => {
+                    if let Some(weapon) = Weapon::from_id(frame.weaponselect) {
+                        self.selected = Some(weapon);
+                    }
+                }
+                DemoEvent::UserMessage(_, CsUserMessage::CurWeapon(cur_weapon)) => {
+                    if cur_weapon.state == 0 {
+                        released = Weapon::from_id(cur_weapon.weapon_id).or(released);
+                    }
+                    self.cur_weapon(cur_weapon)
+                }
+                DemoEvent::WeaponAnimation(..) => {
+                    if let Some(weapon) = self.selected.take() {
+                        self.current = Some(weapon);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if !self.known && self.current.is_some() {
+            self.known = true;
+            self.held_before = released.filter(|&weapon| self.current != Some(weapon));
+        }
+
+        for event in events {
+            if let DemoEvent::NetworkMessage(_, frame) = event {
+                frame.current_weapon = self.current;
+            }
+        }
+    }
+
+    fn cur_weapon(&mut self, cur_weapon: &CurWeapon) {
+        let weapon = Weapon::from_id(cur_weapon.weapon_id);
+
+        if cur_weapon.state != 0 {
+            self.current = weapon;
+            self.selected = None;
+        } else if weapon.is_none_or(|weapon| self.current == Some(weapon)) {
+            // 들고 있던 무기를 내려놓았거나 모든 무기를 잃었다 (번호 0, 255)
+            self.current = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::demo::parse;
+    use crate::reader::DemoReader;
+
+    #[test]
+    fn frames_before_first_switch_hold_the_released_weapon() {
+        let demo = parse("test/274_dcj_Desu.dem").unwrap();
+
+        // 345 프레임에서 칼을 내려놓고(state 0) USP 를 꺼내며, 2563 프레임에서 죽어 모든 무기를 잃는다
+        for frame in &demo.frames {
+            let expected = match frame.frame {
+                ..345 => Some(Weapon::Knife),
+                345..2563 => Some(Weapon::Usp),
+                _ => None,
+            };
+
+            assert_eq!(frame.current_weapon, expected, "frame {}", frame.frame);
+        }
+    }
+
+    #[test]
+    fn streaming_reader_reports_the_released_weapon() {
+        let file = std::fs::File::open("test/274_dcj_Desu.dem").unwrap();
+        let mut reader = DemoReader::new(file).unwrap();
+        let mut unknown = 0;
+
+        for event in reader.by_ref() {
+            if let DemoEvent::NetworkMessage(header, frame) = event.unwrap()
+                && header.frame < 345
+            {
+                assert_eq!(frame.current_weapon, None);
+                unknown += 1;
+            }
+        }
+
+        assert!(unknown > 0);
+        assert_eq!(reader.held_before(), Some(Weapon::Knife));
+    }
+}
@@ -0,0 +1,419 @@
+//! 데모 파일 쓰기
+//!
+//! parse.rs 의 역순으로 헤더, 프레임, 디렉토리를 쓴다. 파싱한 값을 바꾸지 않으면 원본과 같은 바이트가 나온다.
+//! 네트워크 메세지 페이로드는 다시 인코딩하지 않으므로 `MsgDataParseMode::Raw` 로 읽은 데모만 쓸 수 있다.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::parse::{DIRECTORY_ENTRY_SIZE, HEADER_SIZE};
+use crate::types::{
+    ClientData, Demo, DemoInfo, DirectoryEntry, Event, Frame, FrameData, Header, MessageData,
+    MoveVars, NetworkMessage, RefParams, SequenceInfo, Sound, UserCmd, WeaponAnimation,
+};
+
+/// 헤더 디렉토리 오프셋 필드의 위치 (헤더 끝 4바이트)
+const DIRECTORY_OFFSET_POSITION: u64 = HEADER_SIZE as u64 - 4;
+
+impl Demo {
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        DemoWriter::write_demo(file, self)?;
+
+        Ok(())
+    }
+}
+
+/// 헤더, 세그먼트, 프레임 순서로 데모를 쓴다.
+///
+/// 세그먼트의 오프셋과 길이, 헤더의 디렉토리 오프셋은 쓴 위치로 채운다.
+/// 디렉토리는 `finish` 에서 파일 끝에 쓴다.
+pub struct DemoWriter<W: Write + Seek> {
+    writer: W,
+    /// 지금까지 쓴 바이트 수
+    position: usize,
+    entries: Vec<DirectoryEntry>,
+    /// 쓰고 있는 세그먼트
+    segment: Option<DirectoryEntry>,
+}
+
+impl<W: Write + Seek> DemoWriter<W> {
+    /// 헤더를 쓴다. `writer` 는 파일 처음에 있어야 한다.
+    pub fn new(mut writer: W, header: &Header) -> io::Result<Self> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE);
+        put_header(&mut bytes, header);
+        writer.write_all(&bytes)?;
+
+        Ok(DemoWriter {
+            writer,
+            position: bytes.len(),
+            entries: vec![],
+            segment: None,
+        })
+    }
+
+    /// 파싱한 데모를 그대로 쓴다. 세그먼트 뒤에 남아 있던 원본 바이트도 함께 쓴다.
+    pub fn write_demo(writer: W, demo: &Demo) -> io::Result<W> {
+        let mut demo_writer = DemoWriter::new(writer, &demo.header)?;
+
+        for entry in &demo.directory.entries {
+            demo_writer.begin_segment(entry)?;
+            for frame in &entry.frames {
+                demo_writer.write_frame(frame)?;
+            }
+            demo_writer.write_raw(&entry.trailing)?;
+            demo_writer.end_segment()?;
+        }
+
+        demo_writer.finish()
+    }
+
+    /// `entry` 의 타입, 이름, 시간, 프레임 수를 그대로 쓰는 세그먼트를 시작한다.
+    /// 쓰고 있던 세그먼트가 있으면 먼저 닫는다.
+    pub fn begin_segment(&mut self, entry: &DirectoryEntry) -> io::Result<()> {
+        self.end_segment()?;
+
+        self.segment = Some(DirectoryEntry {
+            type_: entry.type_,
+            description: entry.description.clone(),
+            flags: entry.flags,
+            cd_track: entry.cd_track,
+            track_time: entry.track_time,
+            frame_count: entry.frame_count,
+            offset: self.position as i32,
+            file_length: 0,
+            frames: vec![],
+            trailing: vec![],
+        });
+
+        Ok(())
+    }
+
+    pub fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        let mut bytes = vec![];
+        put_frame(&mut bytes, frame)?;
+
+        self.write_raw(&bytes)
+    }
+
+    /// 프레임으로 해석하지 않는 바이트를 그대로 쓴다.
+    pub fn write_raw(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if self.segment.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame written outside of a segment",
+            ));
+        }
+
+        self.writer.write_all(bytes)?;
+        self.position += bytes.len();
+
+        Ok(())
+    }
+
+    /// 쓰고 있는 세그먼트의 길이를 정한다. 세그먼트가 없으면 아무것도 하지 않는다.
+    pub fn end_segment(&mut self) -> io::Result<()> {
+        if let Some(mut entry) = self.segment.take() {
+            entry.file_length = (self.position - entry.offset as usize) as i32;
+            self.entries.push(entry);
+        }
+
+        Ok(())
+    }
+
+    /// 디렉토리를 쓰고 헤더의 디렉토리 오프셋을 고친다.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.end_segment()?;
+
+        let directory_offset = self.position as i32;
+        let mut bytes = Vec::with_capacity(4 + self.entries.len() * DIRECTORY_ENTRY_SIZE);
+        put_i32(&mut bytes, self.entries.len() as i32);
+        for entry in &self.entries {
+            put_directory_entry(&mut bytes, entry);
+        }
+        self.writer.write_all(&bytes)?;
+
+        self.writer
+            .seek(SeekFrom::Start(DIRECTORY_OFFSET_POSITION))?;
+        self.writer.write_all(&directory_offset.to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+fn put_i32(out: &mut Vec<u8>, value: i32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_f32(out: &mut Vec<u8>, value: f32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_point_float(out: &mut Vec<u8>, point: [f32; 3]) {
+    for value in point {
+        put_f32(out, value);
+    }
+}
+
+/// 고정 길이 필드. 짧으면 0 으로 채우고 길면 자른다.
+fn put_fixed(out: &mut Vec<u8>, bytes: &[u8], length: usize) {
+    let bytes = &bytes[..bytes.len().min(length)];
+    out.extend_from_slice(bytes);
+    out.resize(out.len() + length - bytes.len(), 0);
+}
+
+fn put_header(out: &mut Vec<u8>, header: &Header) {
+    put_fixed(out, &header.magic, 8);
+    put_i32(out, header.demo_protocol);
+    put_i32(out, header.network_protocol);
+    put_fixed(out, &header.map_name, 260);
+    put_fixed(out, &header.game_directory, 260);
+    out.extend_from_slice(&header.map_checksum.to_le_bytes());
+    put_i32(out, header.directory_offset);
+}
+
+fn put_directory_entry(out: &mut Vec<u8>, entry: &DirectoryEntry) {
+    put_i32(out, entry.type_);
+    put_fixed(out, &entry.description, 64);
+    put_i32(out, entry.flags);
+    put_i32(out, entry.cd_track);
+    put_f32(out, entry.track_time);
+    put_i32(out, entry.frame_count);
+    put_i32(out, entry.offset);
+    put_i32(out, entry.file_length);
+}
+
+fn put_frame(out: &mut Vec<u8>, frame: &Frame) -> io::Result<()> {
+    out.push(frame.frame_data.frame_type());
+    put_f32(out, frame.time);
+    put_i32(out, frame.frame);
+
+    match &frame.frame_data {
+        FrameData::NetworkMessage(message) => put_network_message(out, &message.1)?,
+        FrameData::DemoStart | FrameData::NextSection => {}
+        FrameData::ConsoleCommand(command) => put_fixed(out, &command.command, 64),
+        FrameData::ClientData(client_data) => put_client_data(out, client_data),
+        FrameData::Event(event) => put_event(out, event),
+        FrameData::WeaponAnimation(animation) => put_weapon_animation(out, animation),
+        FrameData::Sound(sound) => put_sound(out, sound),
+        FrameData::DemoBuffer(buffer) => {
+            put_i32(out, buffer.buffer.len() as i32);
+            out.extend_from_slice(&buffer.buffer);
+        }
+    }
+
+    Ok(())
+}
+
+fn put_network_message(out: &mut Vec<u8>, message: &NetworkMessage) -> io::Result<()> {
+    let MessageData::Raw(payload) = &message.messages else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "network messages must be read with MsgDataParseMode::Raw to be written",
+        ));
+    };
+
+    put_demo_info(out, &message.info);
+    put_sequence_info(out, &message.sequence_info);
+    put_i32(out, payload.len() as i32);
+    out.extend_from_slice(payload);
+
+    Ok(())
+}
+
+fn put_demo_info(out: &mut Vec<u8>, info: &DemoInfo) {
+    put_f32(out, info.timestamp);
+    put_ref_params(out, &info.ref_params);
+    put_user_cmd(out, &info.user_cmd);
+    put_movevars(out, &info.movevars);
+    put_point_float(out, info.view);
+    put_i32(out, info.viewmodel);
+}
+
+fn put_ref_params(out: &mut Vec<u8>, ref_params: &RefParams) {
+    for point in [
+        ref_params.vieworg,
+        ref_params.viewangles,
+        ref_params.forward,
+        ref_params.right,
+        ref_params.up,
+    ] {
+        put_point_float(out, point);
+    }
+    put_f32(out, ref_params.frametime);
+    put_f32(out, ref_params.time);
+
+    for value in [
+        ref_params.intermission,
+        ref_params.paused,
+        ref_params.spectator,
+        ref_params.onground,
+        ref_params.waterlevel,
+    ] {
+        put_i32(out, value);
+    }
+
+    put_point_float(out, ref_params.simvel);
+    put_point_float(out, ref_params.simorg);
+    put_point_float(out, ref_params.viewheight);
+    put_f32(out, ref_params.idealpitch);
+    put_point_float(out, ref_params.cl_viewangles);
+    put_i32(out, ref_params.health);
+    put_point_float(out, ref_params.crosshairangle);
+    put_f32(out, ref_params.viewsize);
+    put_point_float(out, ref_params.punchangle);
+
+    for value in [
+        ref_params.maxclients,
+        ref_params.viewentity,
+        ref_params.playernum,
+        ref_params.max_entities,
+        ref_params.demoplayback,
+        ref_params.hardware,
+        ref_params.smoothing,
+        ref_params.ptr_cmd,
+        ref_params.ptr_movevars,
+    ] {
+        put_i32(out, value);
+    }
+    for value in ref_params.viewport {
+        put_i32(out, value);
+    }
+    put_i32(out, ref_params.next_view);
+    put_i32(out, ref_params.only_client_draw);
+}
+
+fn put_user_cmd(out: &mut Vec<u8>, user_cmd: &UserCmd) {
+    out.extend_from_slice(&user_cmd.lerp_msec.to_le_bytes());
+    out.push(user_cmd.msec);
+    out.push(user_cmd.align_1);
+    put_point_float(out, user_cmd.viewangles);
+    put_f32(out, user_cmd.forwardmove);
+    put_f32(out, user_cmd.sidemove);
+    put_f32(out, user_cmd.upmove);
+    out.extend_from_slice(&user_cmd.lightlevel.to_le_bytes());
+    out.push(user_cmd.align_2);
+    out.extend_from_slice(&user_cmd.buttons.to_le_bytes());
+    out.extend_from_slice(&user_cmd.impulse.to_le_bytes());
+    out.extend_from_slice(&user_cmd.weaponselect.to_le_bytes());
+    out.push(user_cmd.align_3);
+    out.push(user_cmd.align_4);
+    put_i32(out, user_cmd.impact_index);
+    put_point_float(out, user_cmd.impact_position);
+}
+
+fn put_movevars(out: &mut Vec<u8>, movevars: &MoveVars) {
+    for value in [
+        movevars.gravity,
+        movevars.stopspeed,
+        movevars.maxspeed,
+        movevars.spectatormaxspeed,
+        movevars.accelerate,
+        movevars.airaccelerate,
+        movevars.wateraccelerate,
+        movevars.friction,
+        movevars.edgefriction,
+        movevars.waterfriction,
+        movevars.entgravity,
+        movevars.bounce,
+        movevars.stepsize,
+        movevars.maxvelocity,
+        movevars.zmax,
+        movevars.wave_height,
+    ] {
+        put_f32(out, value);
+    }
+    put_i32(out, movevars.footsteps);
+    put_fixed(out, &movevars.sky_name, 32);
+
+    for value in [
+        movevars.rollangle,
+        movevars.rollspeed,
+        movevars.skycolor_r,
+        movevars.skycolor_g,
+        movevars.skycolor_b,
+    ] {
+        put_f32(out, value);
+    }
+    put_point_float(out, movevars.skyvec);
+}
+
+fn put_sequence_info(out: &mut Vec<u8>, sequence_info: &SequenceInfo) {
+    for value in [
+        sequence_info.incoming_sequence,
+        sequence_info.incoming_acknowledged,
+        sequence_info.incoming_reliable_acknowledged,
+        sequence_info.incoming_reliable_sequence,
+        sequence_info.outgoing_sequence,
+        sequence_info.reliable_sequence,
+        sequence_info.last_reliable_sequence,
+    ] {
+        put_i32(out, value);
+    }
+}
+
+fn put_client_data(out: &mut Vec<u8>, client_data: &ClientData) {
+    put_point_float(out, client_data.origin);
+    put_point_float(out, client_data.viewangles);
+    put_i32(out, client_data.weapon_bits);
+    put_f32(out, client_data.fov);
+}
+
+fn put_event(out: &mut Vec<u8>, event: &Event) {
+    put_i32(out, event.flags);
+    put_i32(out, event.index);
+    put_f32(out, event.delay);
+
+    let args = &event.args;
+    put_i32(out, args.flags);
+    put_i32(out, args.entity_index);
+    put_point_float(out, args.origin);
+    put_point_float(out, args.angles);
+    put_point_float(out, args.velocity);
+    put_i32(out, args.ducking);
+    put_f32(out, args.fparam1);
+    put_f32(out, args.fparam2);
+    put_i32(out, args.iparam1);
+    put_i32(out, args.iparam2);
+    put_i32(out, args.bparam1);
+    put_i32(out, args.bparam2);
+}
+
+fn put_weapon_animation(out: &mut Vec<u8>, animation: &WeaponAnimation) {
+    put_i32(out, animation.anim);
+    put_i32(out, animation.body);
+}
+
+fn put_sound(out: &mut Vec<u8>, sound: &Sound) {
+    put_i32(out, sound.channel);
+    put_i32(out, sound.sample.len() as i32);
+    out.extend_from_slice(&sound.sample);
+    put_f32(out, sound.volume);
+    put_f32(out, sound.attenuation);
+    put_i32(out, sound.flags);
+    put_i32(out, sound.pitch);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::parse::{MsgDataParseMode, parse_demo};
+
+    #[test]
+    fn raw_round_trip_is_byte_identical() {
+        let bytes = std::fs::read("test/274_dcj_Desu.dem").unwrap();
+        let (_, demo) = parse_demo(&bytes, MsgDataParseMode::Raw).unwrap();
+
+        let written = DemoWriter::write_demo(Cursor::new(vec![]), &demo)
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(written.len(), bytes.len());
+        assert!(written == bytes, "written demo differs from the original");
+    }
+}